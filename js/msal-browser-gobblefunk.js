@@ -42,4 +42,10 @@ export class SilentRequest {
         this.scopes = scopes;
         this.account = account;
     }
+}
+
+export class AuthorizationCodeRequest {
+    constructor(scopes) {
+        this.scopes = scopes;
+    }
 }
\ No newline at end of file
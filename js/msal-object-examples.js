@@ -129,6 +129,7 @@ const authResponse = {
         environment: "environment",
         tenantId: "tenantId",
         username: "username",
+        idTokenClaims: idToken,
     },
     idToken: "idToken",
     idTokenClaims: idToken,
@@ -138,6 +139,14 @@ const authResponse = {
     extExpiresOn: "Thu Aug 06 2020 10:35:12 GMT+1000 (Australian Eastern Standard Time)",
     state: "state",
     familyId: "familyId",
+    tokenType: "Bearer",
+    correlationId: "8f4b1b4e-3e0f-4f8a-9a55-2a6f8e3c1d7b",
+    cloudGraphHostName: "graph.windows.net",
+    msGraphHost: "graph.microsoft.com",
+    code: "spaCode",
+    fromNativeBroker: false,
+    requestId: "c1f2e3d4-0000-4000-8000-000000000000",
+    refreshOn: "Thu Aug 06 2020 10:05:12 GMT+1000 (Australian Eastern Standard Time)",
 }
 
 const completeToken = {
@@ -213,6 +222,20 @@ const completeToken = {
     wids: [],
     groups: [],
     hasgroups: true,
+    _claim_names: { groups: "src1" },
+    _claim_sources: { src1: { endpoint: "https://graph.microsoft.com/v1.0/users/oid/getMemberObjects" } },
 }
 
-export { msalConfig, authResponse, accessToken, idToken, completeToken, auth, cache, system }
\ No newline at end of file
+// https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-browser/docs/request-response-object.md
+const authorizationUrlRequest = {
+    scopes: ["openid", "profile"],
+    authority: "authority",
+    prompt: "select_account",
+    responseMode: "fragment",
+    loginHint: "login_hint",
+    extraQueryParameters: {
+        extra_query_key: "extra_query_value",
+    },
+}
+
+export { msalConfig, authResponse, accessToken, idToken, completeToken, auth, cache, system, authorizationUrlRequest }
\ No newline at end of file
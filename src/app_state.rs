@@ -0,0 +1,198 @@
+//! Typed application state carried through the `state` request parameter, so the app
+//! can pick up where the user left off once the popup / redirect completes.
+//! msal already rejects a response whose state doesn't match the one it sent. The checksum
+//! here is unkeyed so only catches accidental damage, such as a truncated url, anyone can
+//! recompute it after editing the payload. Don't put anything in the state that needs to
+//! be trusted.
+
+use crate::AuthenticationResult;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+/// The state is sent in the url so needs to be kept short
+pub const DEFAULT_MAX_LENGTH: usize = 1024;
+const CHECKSUM_BYTES: usize = 8;
+const SEPARATOR: char = '.';
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AppStateError {
+    /// No state on the result
+    Missing,
+    TooLarge {
+        length: usize,
+        max_length: usize,
+    },
+    /// The state isn't one produced by [`AppState::encode`] or its checksum doesn't match,
+    /// this is not an integrity check
+    Corrupt,
+    Json(String),
+}
+
+impl fmt::Display for AppStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "no state was returned"),
+            Self::TooLarge { length, max_length } => write!(
+                f,
+                "encoded state is {} characters, the maximum is {}",
+                length, max_length
+            ),
+            Self::Corrupt => write!(f, "state is corrupt"),
+            Self::Json(e) => write!(f, "state json error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppStateError {}
+
+impl From<AppStateError> for JsValue {
+    fn from(err: AppStateError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+impl From<serde_json::Error> for AppStateError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err.to_string())
+    }
+}
+
+/// ```ignore
+/// let state = AppState::new(Page { path: "/orders".into() }).encode()?;
+/// let request = RedirectRequest::new(&scopes[..]).set_state(state);
+/// // Once back from the redirect or popup
+/// let page = AppState::<Page>::from_result(&auth_result)?.into_inner();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppState<T> {
+    value: T,
+    max_length: usize,
+}
+
+impl<T> AppState<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            max_length: DEFAULT_MAX_LENGTH,
+        }
+    }
+
+    pub fn set_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Produces the string to pass to `set_state`
+    pub fn encode(&self) -> Result<String, AppStateError> {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&self.value)?);
+        let state = format!("{}{}{}", checksum(&payload), SEPARATOR, payload);
+        if state.len() > self.max_length {
+            Err(AppStateError::TooLarge {
+                length: state.len(),
+                max_length: self.max_length,
+            })
+        } else {
+            Ok(state)
+        }
+    }
+
+    pub fn decode(state: &str) -> Result<Self, AppStateError> {
+        if state.is_empty() {
+            return Err(AppStateError::Missing);
+        }
+        let (check, payload) = state.split_once(SEPARATOR).ok_or(AppStateError::Corrupt)?;
+        if check != checksum(payload) {
+            return Err(AppStateError::Corrupt);
+        }
+        let json = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|_| AppStateError::Corrupt)?;
+        Ok(Self::new(serde_json::from_slice(&json)?))
+    }
+
+    /// Works with the result of both the popup and redirect flows
+    pub fn from_result(auth_result: &AuthenticationResult) -> Result<Self, AppStateError> {
+        Self::decode(auth_result.state().ok_or(AppStateError::Missing)?)
+    }
+}
+
+/// Detects accidental damage only, it isn't keyed
+fn checksum(payload: &str) -> String {
+    URL_SAFE_NO_PAD.encode(&Sha256::digest(payload.as_bytes())[..CHECKSUM_BYTES])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Page {
+        path: String,
+        unsaved: Option<String>,
+    }
+
+    fn page() -> Page {
+        Page {
+            path: "/orders/42".into(),
+            unsaved: Some("half written comment".into()),
+        }
+    }
+
+    #[test]
+    fn round_trip_state() {
+        let state = AppState::new(page()).encode().unwrap();
+        assert_eq!(
+            AppState::<Page>::decode(&state).unwrap().into_inner(),
+            page()
+        );
+    }
+
+    #[test]
+    fn invalid_state() {
+        let state = AppState::new(page()).encode().unwrap();
+        let (check, payload) = state.split_once(SEPARATOR).unwrap();
+        let edited = format!("{}{}{}", check, SEPARATOR, &payload[1..]);
+
+        assert_eq!(AppState::<Page>::decode(""), Err(AppStateError::Missing));
+        assert_eq!(
+            AppState::<Page>::decode("not app state"),
+            Err(AppStateError::Corrupt)
+        );
+        assert_eq!(
+            AppState::<Page>::decode(&edited),
+            Err(AppStateError::Corrupt)
+        );
+        assert!(matches!(
+            AppState::<u32>::decode(&state),
+            Err(AppStateError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn state_too_large() {
+        let state = AppState::new(page()).set_max_length(10);
+        assert!(matches!(
+            state.encode(),
+            Err(AppStateError::TooLarge { max_length: 10, .. })
+        ));
+    }
+}
@@ -0,0 +1,476 @@
+//! A read-only view of what msal has cached, for a settings page or debugging. Entries are
+//! read from the configured storage following msal's cache schema, the secrets are dropped while
+//! parsing and never kept. Nothing is listed for `memoryStorage`.
+//! https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-common/docs/cache.md
+
+use crate::{msal, time};
+use js_sys::{Object, Reflect};
+use serde_json::{Map, Value};
+use std::time::SystemTime;
+use wasm_bindgen::{prelude::*, JsCast};
+
+/// msal's default `cacheLocation`
+const DEFAULT_CACHE_LOCATION: &str = "sessionStorage";
+
+#[wasm_bindgen]
+extern "C" {
+    type Storage;
+
+    #[wasm_bindgen(method, catch, js_name = getItem)]
+    fn get_item(this: &Storage, key: &str) -> Result<Option<String>, JsValue>;
+}
+
+/// The fields shared by all the credentials
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedCredential {
+    key: String,
+    home_account_id: String,
+    environment: String,
+    client_id: String,
+    realm: Option<String>,
+}
+
+impl CachedCredential {
+    fn from_entity(key: &str, entity: &Map<String, Value>) -> Self {
+        Self {
+            key: key.to_owned(),
+            home_account_id: string(entity, "homeAccountId").unwrap_or_default(),
+            environment: string(entity, "environment").unwrap_or_default(),
+            client_id: string(entity, "clientId").unwrap_or_default(),
+            realm: string(entity, "realm"),
+        }
+    }
+
+    /// The storage key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn home_account_id(&self) -> &str {
+        &self.home_account_id
+    }
+
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// The tenant id
+    pub fn realm(&self) -> Option<&str> {
+        self.realm.as_deref()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedAccount {
+    key: String,
+    home_account_id: String,
+    environment: String,
+    tenant_id: String,
+    local_account_id: String,
+    username: String,
+    name: Option<String>,
+    authority_type: String,
+}
+
+impl CachedAccount {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn home_account_id(&self) -> &str {
+        &self.home_account_id
+    }
+
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    pub fn tenant_id(&self) -> &str {
+        &self.tenant_id
+    }
+
+    pub fn local_account_id(&self) -> &str {
+        &self.local_account_id
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// `MSSTS`, `ADFS`, `MSA` or `Generic`
+    pub fn authority_type(&self) -> &str {
+        &self.authority_type
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedAccessToken {
+    credential: CachedCredential,
+    target: String,
+    token_type: String,
+    cached_at: Option<SystemTime>,
+    expires_on: Option<SystemTime>,
+    extended_expires_on: Option<SystemTime>,
+    refresh_on: Option<SystemTime>,
+}
+
+impl CachedAccessToken {
+    pub fn credential(&self) -> &CachedCredential {
+        &self.credential
+    }
+
+    /// The scopes as stored by msal, space separated
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn scopes(&self) -> Vec<&str> {
+        self.target.split_whitespace().collect()
+    }
+
+    /// `Bearer`, `pop` or `ssh-cert`
+    pub fn token_type(&self) -> &str {
+        &self.token_type
+    }
+
+    pub fn cached_at(&self) -> Option<&SystemTime> {
+        self.cached_at.as_ref()
+    }
+
+    pub fn expires_on(&self) -> Option<&SystemTime> {
+        self.expires_on.as_ref()
+    }
+
+    pub fn extended_expires_on(&self) -> Option<&SystemTime> {
+        self.extended_expires_on.as_ref()
+    }
+
+    pub fn refresh_on(&self) -> Option<&SystemTime> {
+        self.refresh_on.as_ref()
+    }
+
+    /// msal won't return it, an unknown expiry counts as expired
+    pub fn is_expired(&self) -> bool {
+        self.expires_on
+            .is_none_or(|v| time::is_expired(v, Default::default()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedRefreshToken {
+    credential: CachedCredential,
+    family_id: Option<String>,
+}
+
+impl CachedRefreshToken {
+    pub fn credential(&self) -> &CachedCredential {
+        &self.credential
+    }
+
+    /// Set when the token can be used by the other apps in the family
+    pub fn family_id(&self) -> Option<&str> {
+        self.family_id.as_deref()
+    }
+}
+
+/// The cache entries when the snapshot was taken
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheSnapshot {
+    accounts: Vec<CachedAccount>,
+    id_tokens: Vec<CachedCredential>,
+    access_tokens: Vec<CachedAccessToken>,
+    refresh_tokens: Vec<CachedRefreshToken>,
+}
+
+impl CacheSnapshot {
+    /// Sorts the entries out of `(key, value)` pairs from storage, other values are ignored
+    pub fn from_entries<I, K, V>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut snapshot = Self::default();
+        for (key, value) in entries {
+            if let Ok(Value::Object(entity)) = serde_json::from_str(value.as_ref()) {
+                snapshot.push(key.as_ref(), &entity)
+            }
+        }
+        snapshot
+    }
+
+    fn push(&mut self, key: &str, entity: &Map<String, Value>) {
+        let credential = || CachedCredential::from_entity(key, entity);
+        match string(entity, "credentialType").as_deref() {
+            Some("IdToken") => self.id_tokens.push(credential()),
+            Some("AccessToken" | "AccessToken_With_AuthScheme") => {
+                self.access_tokens.push(CachedAccessToken {
+                    credential: credential(),
+                    target: string(entity, "target").unwrap_or_default(),
+                    token_type: string(entity, "tokenType").unwrap_or_else(|| "Bearer".into()),
+                    cached_at: seconds(entity, "cachedAt"),
+                    expires_on: seconds(entity, "expiresOn"),
+                    extended_expires_on: seconds(entity, "extendedExpiresOn"),
+                    refresh_on: seconds(entity, "refreshOn"),
+                })
+            }
+            Some("RefreshToken") => self.refresh_tokens.push(CachedRefreshToken {
+                credential: credential(),
+                family_id: string(entity, "familyId"),
+            }),
+            Some(_) => {}
+            // The same check as msal's `AccountEntity.isAccountEntity`
+            None => {
+                // Present but possibly empty, e.g. the `realm` of some authorities
+                let field = |name| entity.get(name).and_then(Value::as_str).map(str::to_owned);
+                if let (
+                    Some(home_account_id),
+                    Some(environment),
+                    Some(tenant_id),
+                    Some(local_account_id),
+                    Some(username),
+                    Some(authority_type),
+                ) = (
+                    field("homeAccountId"),
+                    field("environment"),
+                    field("realm"),
+                    field("localAccountId"),
+                    field("username"),
+                    field("authorityType"),
+                ) {
+                    self.accounts.push(CachedAccount {
+                        key: key.to_owned(),
+                        home_account_id,
+                        environment,
+                        tenant_id,
+                        local_account_id,
+                        username,
+                        name: string(entity, "name"),
+                        authority_type,
+                    })
+                }
+            }
+        }
+    }
+
+    pub fn accounts(&self) -> &[CachedAccount] {
+        &self.accounts
+    }
+
+    pub fn id_tokens(&self) -> &[CachedCredential] {
+        &self.id_tokens
+    }
+
+    pub fn access_tokens(&self) -> &[CachedAccessToken] {
+        &self.access_tokens
+    }
+
+    pub fn refresh_tokens(&self) -> &[CachedRefreshToken] {
+        &self.refresh_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+            && self.id_tokens.is_empty()
+            && self.access_tokens.is_empty()
+            && self.refresh_tokens.is_empty()
+    }
+}
+
+fn string(entity: &Map<String, Value>, name: &str) -> Option<String> {
+    entity
+        .get(name)
+        .and_then(Value::as_str)
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+}
+
+// msal stores the times as strings of seconds
+fn seconds(entity: &Map<String, Value>, name: &str) -> Option<SystemTime> {
+    match entity.get(name)? {
+        Value::String(v) => v.parse().ok(),
+        Value::Number(v) => v.as_f64(),
+        _ => None,
+    }
+    .map(time::from_seconds)
+}
+
+/// The configured `cacheLocation`, msal falls back to `memoryStorage` if it isn't available
+pub(crate) fn cache_location(client_app: &msal::PublicClientApplication) -> String {
+    client_app
+        .config()
+        .cache()
+        .and_then(|v| v.cache_location())
+        .unwrap_or_else(|| DEFAULT_CACHE_LOCATION.to_owned())
+}
+
+/// The object msal reads and writes the cache through, `localStorage` or `sessionStorage`
+/// unless replaced. `None` for `memoryStorage`.
+pub(crate) fn window_storage(client_app: &msal::PublicClientApplication) -> Option<Object> {
+    let browser_storage = Reflect::get(client_app, &"browserStorage".into()).ok()?;
+    let storage = Reflect::get(&browser_storage, &"browserStorage".into()).ok()?;
+    Reflect::get(&storage, &"windowStorage".into())
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// Reads the storage msal is configured with
+pub(crate) fn snapshot(
+    client_app: &msal::PublicClientApplication,
+) -> Result<CacheSnapshot, JsValue> {
+    let storage = match window_storage(client_app) {
+        Some(v) => v,
+        None => match cache_location(client_app).as_str() {
+            location @ ("localStorage" | "sessionStorage") => {
+                match Reflect::get(&js_sys::global(), &location.into())?.dyn_into() {
+                    Ok(v) => v,
+                    Err(_) => return Ok(CacheSnapshot::default()),
+                }
+            }
+            _ => return Ok(CacheSnapshot::default()),
+        },
+    };
+    let mut entries = Vec::new();
+    // The same as msal's `getKeys`
+    for key in Object::keys(&storage).iter().filter_map(|v| v.as_string()) {
+        if let Some(value) = storage.unchecked_ref::<Storage>().get_item(&key)? {
+            entries.push((key, value));
+        }
+    }
+    Ok(CacheSnapshot::from_entries(entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const HOME_ACCOUNT_ID: &str = "uid.utid";
+    const CLIENT_ID: &str = "client_id";
+
+    fn entries() -> Vec<(String, String)> {
+        let entry = |key: &str, value: Value| (key.to_owned(), value.to_string());
+        vec![
+            entry(
+                "uid.utid-login.microsoftonline.com-utid",
+                serde_json::json!({
+                    "homeAccountId": HOME_ACCOUNT_ID,
+                    "environment": "login.microsoftonline.com",
+                    "realm": "utid",
+                    "localAccountId": "uid",
+                    "username": "user@contoso.com",
+                    "name": "A User",
+                    "authorityType": "MSSTS",
+                    "clientInfo": "eyJ1aWQiOiJ1aWQifQ"
+                }),
+            ),
+            entry(
+                "uid.utid-login.microsoftonline.com-idtoken-client_id-utid---",
+                serde_json::json!({
+                    "homeAccountId": HOME_ACCOUNT_ID,
+                    "environment": "login.microsoftonline.com",
+                    "credentialType": "IdToken",
+                    "clientId": CLIENT_ID,
+                    "realm": "utid",
+                    "secret": "header.payload.signature"
+                }),
+            ),
+            entry(
+                "uid.utid-login.microsoftonline.com-accesstoken-client_id-utid-user.read openid--",
+                serde_json::json!({
+                    "homeAccountId": HOME_ACCOUNT_ID,
+                    "environment": "login.microsoftonline.com",
+                    "credentialType": "AccessToken",
+                    "clientId": CLIENT_ID,
+                    "realm": "utid",
+                    "target": "User.Read openid",
+                    "tokenType": "Bearer",
+                    "cachedAt": "1596672312",
+                    "expiresOn": "1596675912",
+                    "extendedExpiresOn": "1596679512",
+                    "secret": "accessToken"
+                }),
+            ),
+            entry(
+                "uid.utid-login.microsoftonline.com-refreshtoken-client_id----",
+                serde_json::json!({
+                    "homeAccountId": HOME_ACCOUNT_ID,
+                    "environment": "login.microsoftonline.com",
+                    "credentialType": "RefreshToken",
+                    "clientId": CLIENT_ID,
+                    "familyId": "1",
+                    "secret": "refreshToken"
+                }),
+            ),
+            // Not cache entities
+            entry("msal.client_id.active-account", Value::String("uid".into())),
+            ("not-json".to_owned(), "{".to_owned()),
+            entry(
+                "server-telemetry-client_id",
+                serde_json::json!({"failedRequests": [], "errors": [], "cacheHits": 1}),
+            ),
+        ]
+    }
+
+    #[test]
+    fn parse_cache_entries() {
+        let snapshot = CacheSnapshot::from_entries(entries());
+        assert!(!snapshot.is_empty());
+
+        let [account] = snapshot.accounts() else {
+            panic!("{:?}", snapshot.accounts())
+        };
+        assert_eq!(account.home_account_id(), HOME_ACCOUNT_ID);
+        assert_eq!(account.tenant_id(), "utid");
+        assert_eq!(account.username(), "user@contoso.com");
+        assert_eq!(account.name(), Some("A User"));
+        assert_eq!(account.authority_type(), "MSSTS");
+
+        let [id_token] = snapshot.id_tokens() else {
+            panic!("{:?}", snapshot.id_tokens())
+        };
+        assert_eq!(id_token.client_id(), CLIENT_ID);
+        assert_eq!(id_token.realm(), Some("utid"));
+
+        let [access_token] = snapshot.access_tokens() else {
+            panic!("{:?}", snapshot.access_tokens())
+        };
+        assert_eq!(access_token.credential().home_account_id(), HOME_ACCOUNT_ID);
+        assert_eq!(access_token.scopes(), ["User.Read", "openid"]);
+        assert_eq!(access_token.token_type(), "Bearer");
+        assert_eq!(
+            access_token.expires_on(),
+            Some(&(UNIX_EPOCH + Duration::from_secs(1596675912)))
+        );
+        assert_eq!(access_token.refresh_on(), None);
+        assert!(access_token.is_expired());
+
+        let [refresh_token] = snapshot.refresh_tokens() else {
+            panic!("{:?}", snapshot.refresh_tokens())
+        };
+        assert_eq!(refresh_token.family_id(), Some("1"));
+        assert_eq!(refresh_token.credential().realm(), None);
+    }
+
+    #[test]
+    fn never_keeps_secrets() {
+        let snapshot = format!("{:?}", CacheSnapshot::from_entries(entries()));
+        for secret in [
+            "header.payload.signature",
+            "accessToken",
+            "refreshToken",
+            "eyJ1aWQiOiJ1aWQifQ",
+        ] {
+            assert!(!snapshot.contains(secret), "{}", secret);
+        }
+    }
+}
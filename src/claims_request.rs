@@ -0,0 +1,344 @@
+//! Builder for the OIDC `claims` request parameter
+//! https://openid.net/specs/openid-connect-core-1_0.html#ClaimsParameter
+//! https://docs.microsoft.com/en-us/azure/active-directory/develop/claims-challenge
+
+use serde_json::{Map, Value};
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+use wasm_bindgen::JsValue;
+
+const ID_TOKEN: &str = "id_token";
+const ACCESS_TOKEN: &str = "access_token";
+const USERINFO: &str = "userinfo";
+const ESSENTIAL: &str = "essential";
+const VALUE: &str = "value";
+const VALUES: &str = "values";
+/// Client capabilities, `cp1` tells the server the app can handle claims challenges (CAE)
+const XMS_CC: &str = "xms_cc";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClaimsRequestError {
+    Json(String),
+    NotAnObject,
+}
+
+impl fmt::Display for ClaimsRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(err) => write!(f, "claims are not valid json: {}", err),
+            Self::NotAnObject => write!(f, "claims must be a json object"),
+        }
+    }
+}
+
+impl std::error::Error for ClaimsRequestError {}
+
+impl From<ClaimsRequestError> for JsValue {
+    fn from(err: ClaimsRequestError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+/// A single requested claim, if nothing is set it is requested as a voluntary claim (`null`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClaimRequest {
+    essential: Option<bool>,
+    value: Option<Value>,
+    values: Option<Vec<Value>>,
+}
+
+impl ClaimRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_essential(mut self, essential: bool) -> Self {
+        self.essential = Some(essential);
+        self
+    }
+
+    /// Usually a string, but numbers and booleans are kept as they are
+    pub fn set_value<T>(mut self, value: T) -> Self
+    where
+        T: Into<Value>,
+    {
+        self.value = Some(value.into());
+        self
+    }
+
+    pub fn set_values<T>(mut self, values: &[T]) -> Self
+    where
+        T: Into<Value> + Clone,
+    {
+        self.values = Some(values.iter().cloned().map(Into::into).collect());
+        self
+    }
+
+    pub fn essential(&self) -> Option<bool> {
+        self.essential
+    }
+
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    pub fn values(&self) -> Option<&[Value]> {
+        self.values.as_deref()
+    }
+
+    /// Anything set on `other` takes precedence
+    fn merge(self, other: Self) -> Self {
+        Self {
+            essential: other.essential.or(self.essential),
+            value: other.value.or(self.value),
+            values: other.values.or(self.values),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        if self == &Self::default() {
+            return Value::Null;
+        }
+        let mut json = Map::new();
+        if let Some(v) = self.essential {
+            json.insert(ESSENTIAL.to_owned(), v.into());
+        }
+        if let Some(v) = &self.value {
+            json.insert(VALUE.to_owned(), v.clone());
+        }
+        if let Some(v) = &self.values {
+            json.insert(VALUES.to_owned(), v.clone().into());
+        }
+        json.into()
+    }
+
+    fn from_value(json: &Value) -> Self {
+        Self {
+            essential: json.get(ESSENTIAL).and_then(Value::as_bool),
+            value: json.get(VALUE).filter(|v| !v.is_null()).cloned(),
+            values: json.get(VALUES).and_then(Value::as_array).cloned(),
+        }
+    }
+}
+
+type Claims = BTreeMap<String, ClaimRequest>;
+
+/// Produces the json expected by `set_claims` on the requests
+/// ```ignore
+/// let claims = ClaimsRequest::new()
+///     .set_id_token_claim("auth_time", ClaimRequest::new().set_essential(true))
+///     .set_client_capabilities(&["cp1"]);
+/// let request = AuthorizationUrlRequest::new(&scopes[..]).set_claims(claims);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClaimsRequest {
+    id_token: Claims,
+    access_token: Claims,
+    userinfo: Claims,
+}
+
+impl ClaimsRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses an existing claims json string, e.g. the decoded claims challenge
+    /// from a `WWW-Authenticate` header
+    pub fn from_json(json: &str) -> Result<Self, ClaimsRequestError> {
+        let json = match serde_json::from_str(json) {
+            Ok(Value::Object(v)) => v,
+            Ok(_) => return Err(ClaimsRequestError::NotAnObject),
+            Err(e) => return Err(ClaimsRequestError::Json(e.to_string())),
+        };
+        let section = |key: &str| {
+            json.get(key)
+                .and_then(Value::as_object)
+                .map(|v| {
+                    v.iter()
+                        .map(|(k, v)| (k.clone(), ClaimRequest::from_value(v)))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            id_token: section(ID_TOKEN),
+            access_token: section(ACCESS_TOKEN),
+            userinfo: section(USERINFO),
+        })
+    }
+
+    pub fn set_id_token_claim<T>(mut self, name: T, claim: ClaimRequest) -> Self
+    where
+        T: Into<String>,
+    {
+        self.id_token.insert(name.into(), claim);
+        self
+    }
+
+    pub fn set_access_token_claim<T>(mut self, name: T, claim: ClaimRequest) -> Self
+    where
+        T: Into<String>,
+    {
+        self.access_token.insert(name.into(), claim);
+        self
+    }
+
+    pub fn set_userinfo_claim<T>(mut self, name: T, claim: ClaimRequest) -> Self
+    where
+        T: Into<String>,
+    {
+        self.userinfo.insert(name.into(), claim);
+        self
+    }
+
+    /// Sets the `xms_cc` access token claim, which is what msal does with `clientCapabilities`
+    pub fn set_client_capabilities<T>(self, client_capabilities: &[T]) -> Self
+    where
+        T: Into<String> + Clone,
+    {
+        let values = client_capabilities
+            .iter()
+            .cloned()
+            .map(|v| Value::String(v.into()))
+            .collect::<Vec<_>>();
+        self.set_access_token_claim(XMS_CC, ClaimRequest::new().set_values(&values))
+    }
+
+    /// Combines two requests, where both have the same claim `other` takes precedence
+    pub fn merge(self, other: Self) -> Self {
+        fn merge_claims(mut claims: Claims, other: Claims) -> Claims {
+            for (k, v) in other {
+                let claim = match claims.remove(&k) {
+                    Some(existing) => existing.merge(v),
+                    None => v,
+                };
+                claims.insert(k, claim);
+            }
+            claims
+        }
+        Self {
+            id_token: merge_claims(self.id_token, other.id_token),
+            access_token: merge_claims(self.access_token, other.access_token),
+            userinfo: merge_claims(self.userinfo, other.userinfo),
+        }
+    }
+
+    pub fn id_token(&self) -> &BTreeMap<String, ClaimRequest> {
+        &self.id_token
+    }
+
+    pub fn access_token(&self) -> &BTreeMap<String, ClaimRequest> {
+        &self.access_token
+    }
+
+    pub fn userinfo(&self) -> &BTreeMap<String, ClaimRequest> {
+        &self.userinfo
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id_token.is_empty() && self.access_token.is_empty() && self.userinfo.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = Map::new();
+        for (key, claims) in [
+            (ID_TOKEN, &self.id_token),
+            (ACCESS_TOKEN, &self.access_token),
+            (USERINFO, &self.userinfo),
+        ] {
+            // Empty sections are left out
+            if !claims.is_empty() {
+                let section = claims
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_value()))
+                    .collect::<Map<_, _>>();
+                json.insert(key.to_owned(), section.into());
+            }
+        }
+        Value::Object(json).to_string()
+    }
+}
+
+impl<'a> From<ClaimsRequest> for Cow<'a, str> {
+    fn from(claims: ClaimsRequest) -> Self {
+        Cow::Owned(claims.to_json())
+    }
+}
+
+impl<'a, 'b> From<&'b ClaimsRequest> for Cow<'a, str> {
+    fn from(claims: &'b ClaimsRequest) -> Self {
+        Cow::Owned(claims.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::requests::{
+        AuthorizationUrlRequest, GetAuthorizationUrlRequest, SetAuthorizationUrlRequest,
+    };
+    use crate::tests::SCOPE;
+    use serde_json::json;
+    use wasm_bindgen_test::*;
+
+    // Claims challenge as returned in a `WWW-Authenticate` header by CAE
+    const CAE_CHALLENGE: &str =
+        r#"{"access_token":{"nbf":{"essential":true,"value":"1604106651"}}}"#;
+
+    #[test]
+    fn build_claims_request() {
+        let claims = ClaimsRequest::new()
+            .set_id_token_claim("auth_time", ClaimRequest::new().set_essential(true))
+            .set_id_token_claim("email", ClaimRequest::new())
+            .set_userinfo_claim(
+                "acr",
+                ClaimRequest::new().set_values(&["urn:mace:incommon:iap:silver"]),
+            );
+
+        assert_eq!(
+            claims.to_json(),
+            r#"{"id_token":{"auth_time":{"essential":true},"email":null},"userinfo":{"acr":{"values":["urn:mace:incommon:iap:silver"]}}}"#
+        );
+        assert_eq!(ClaimsRequest::from_json(&claims.to_json()), Ok(claims));
+    }
+
+    #[test]
+    fn merge_claims_challenge() {
+        let challenge = ClaimsRequest::from_json(CAE_CHALLENGE).unwrap();
+        let claims = challenge.merge(ClaimsRequest::new().set_client_capabilities(&["cp1"]));
+
+        assert_eq!(
+            claims.to_json(),
+            r#"{"access_token":{"nbf":{"essential":true,"value":"1604106651"},"xms_cc":{"values":["cp1"]}}}"#
+        );
+        assert_eq!(
+            ClaimsRequest::from_json("[]"),
+            Err(ClaimsRequestError::NotAnObject)
+        );
+        assert!(matches!(
+            ClaimsRequest::from_json("{"),
+            Err(ClaimsRequestError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_value_types() {
+        let json =
+            r#"{"id_token":{"acr":{"values":["1",2]},"max_age":{"essential":false,"value":1}}}"#;
+        let claims = ClaimsRequest::from_json(json).unwrap();
+        assert_eq!(claims.id_token()["max_age"].value(), Some(&json!(1)));
+        assert_eq!(
+            claims.id_token()["acr"].values(),
+            Some(&[json!("1"), json!(2)][..])
+        );
+        assert_eq!(claims.to_json(), json);
+    }
+
+    #[wasm_bindgen_test]
+    fn set_claims_on_request() {
+        let claims = ClaimsRequest::new().set_client_capabilities(&["cp1"]);
+        let req = AuthorizationUrlRequest::new(&[SCOPE][..]).set_claims(&claims);
+        assert_eq!(req.claims(), Some(claims.to_json().as_str()));
+    }
+}
@@ -0,0 +1,780 @@
+//! Attaches tokens to `fetch` requests, the equivalent of the `MsalInterceptor` in msal-angular.
+//! A [`ProtectedResourceMap`] picks the scopes for each request, then the token is acquired
+//! silently, falling back to a popup or redirect when interaction is required.
+//! https://learn.microsoft.com/en-us/azure/active-directory/develop/claims-challenge
+
+use crate::{
+    acquire_token_silent, msal,
+    requests::{
+        AuthenticationScheme, AuthorizationUrlRequest, InteractionType, SetAuthorizationUrlRequest,
+        SetBaseAuthRequest, SilentRequest,
+    },
+    token_manager::{TokenKey, TokenManager},
+    AccountInfo, AuthenticationResult, PublicClientApplication,
+};
+use base64::{
+    engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use js_sys::{Function, Promise, Reflect};
+use std::fmt;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, Response};
+
+const UNAUTHORIZED: u16 = 401;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(request: &Request) -> Promise;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientError {
+    /// There is no signed in account and the interaction type is `Silent`
+    NoAccount,
+    /// The page is navigating away to sign in, the request should be sent again after
+    Redirecting,
+    /// The interaction type is `Redirect` but the `redirect` feature isn't enabled
+    RedirectNotEnabled,
+    /// An error from msal or `fetch`
+    Js(JsValue),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAccount => write!(f, "no account is signed in"),
+            Self::Redirecting => write!(f, "redirecting to sign in"),
+            Self::RedirectNotEnabled => write!(f, "the `redirect` feature is not enabled"),
+            Self::Js(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<JsValue> for ClientError {
+    fn from(err: JsValue) -> Self {
+        Self::Js(err)
+    }
+}
+
+impl From<ClientError> for JsValue {
+    fn from(err: ClientError) -> Self {
+        match err {
+            ClientError::Js(v) => v,
+            err => js_sys::Error::new(&err.to_string()).into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct UrlPattern {
+    scheme: Option<String>,
+    host: Option<String>,
+    path: String,
+    // The number of literal characters, the most specific pattern wins
+    specificity: usize,
+}
+
+impl UrlPattern {
+    fn new(pattern: &str, origin: Option<&str>) -> Self {
+        let absolute;
+        let pattern = match origin {
+            Some(origin) if pattern.starts_with('/') => {
+                absolute = format!("{}{}", origin.trim_end_matches('/'), pattern);
+                &absolute
+            }
+            _ => pattern,
+        };
+        let (scheme, host, path) = split_url(pattern);
+        Self {
+            scheme: scheme.map(str::to_lowercase),
+            host: host.map(str::to_lowercase),
+            // Without a path every path on the host matches
+            path: match (host, path) {
+                (Some(_), "" | "/") => "*".to_owned(),
+                _ => path.to_owned(),
+            },
+            specificity: pattern.chars().filter(|c| *c != '*').count(),
+        }
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        let (scheme, host, path) = split_url(url);
+        let path = if path.is_empty() { "/" } else { path };
+        // A relative pattern only matches relative urls
+        let part = |pattern: &Option<String>, value: Option<&str>| match (pattern, value) {
+            (Some(pattern), Some(v)) => glob(pattern, &v.to_lowercase()),
+            (None, None) => true,
+            _ => false,
+        };
+        part(&self.scheme, scheme) && part(&self.host, host) && glob(&self.path, path)
+    }
+}
+
+/// Splits into the scheme, host (with any port) and path, dropping the query and fragment
+fn split_url(url: &str) -> (Option<&str>, Option<&str>, &str) {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (host, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+            (Some(scheme), Some(host), path)
+        }
+        None => (None, None, url),
+    }
+}
+
+/// `*` matches any run of characters, including none
+fn glob(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ProtectedResource {
+    pattern: UrlPattern,
+    methods: Option<Vec<String>>,
+    scopes: Vec<String>,
+}
+
+/// The origin of the page, like msal-angular's `getAbsoluteUrl`
+fn page_origin() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::window().and_then(|v| v.location().origin().ok())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        None
+    }
+}
+
+/// Maps urls to the scopes of the token to send with them
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProtectedResourceMap {
+    resources: Vec<ProtectedResource>,
+    // Path only patterns are resolved against it
+    origin: Option<String>,
+}
+
+impl Default for ProtectedResourceMap {
+    fn default() -> Self {
+        Self {
+            resources: Vec::new(),
+            origin: page_origin(),
+        }
+    }
+}
+
+impl ProtectedResourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `pattern` is `scheme://host/path` and `*` matches any characters, e.g.
+    /// `https://*.contoso.com/api/*`. Without a path every path on the host matches, and a
+    /// pattern that is only a path, such as `/api/*`, is on the origin of the page. Empty
+    /// `scopes` exclude the urls, so no token is sent.
+    pub fn add<T>(self, pattern: &str, scopes: &[T]) -> Self
+    where
+        T: Into<String> + Clone,
+    {
+        self.add_resource(pattern, None, scopes)
+    }
+
+    /// As [`add`](Self::add), but only for requests with one of the http `methods`
+    pub fn add_for_methods<T>(self, pattern: &str, methods: &[&str], scopes: &[T]) -> Self
+    where
+        T: Into<String> + Clone,
+    {
+        let methods = methods.iter().map(|v| v.to_uppercase()).collect();
+        self.add_resource(pattern, Some(methods), scopes)
+    }
+
+    fn add_resource<T>(mut self, pattern: &str, methods: Option<Vec<String>>, scopes: &[T]) -> Self
+    where
+        T: Into<String> + Clone,
+    {
+        self.resources.push(ProtectedResource {
+            pattern: UrlPattern::new(pattern, self.origin.as_deref()),
+            methods,
+            scopes: scopes.iter().cloned().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// The scopes of the most specific pattern that matches, the first added wins a tie.
+    /// `None` if no token should be sent.
+    pub fn scopes_for(&self, url: &str, method: &str) -> Option<&[String]> {
+        let method = method.to_uppercase();
+        self.resources
+            .iter()
+            .filter(|v| v.pattern.matches(url))
+            .filter(|v| v.methods.as_ref().is_none_or(|m| m.contains(&method)))
+            .rev()
+            .max_by_key(|v| v.pattern.specificity)
+            .map(|v| v.scopes.as_slice())
+            .filter(|v| !v.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+/// The decoded `claims` of a `WWW-Authenticate` header, sent with a 401 when the token
+/// doesn't satisfy a conditional access policy
+pub fn claims_challenge(www_authenticate: &str) -> Option<String> {
+    let mut rest = www_authenticate;
+    while let Some(i) = rest.find('=') {
+        let name = rest[..i]
+            .rsplit(|c: char| c == ',' || c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        let (value, next) = match rest[i + 1..].strip_prefix('"') {
+            Some(quoted) => quoted
+                .split_once('"')
+                .unwrap_or((quoted, Default::default())),
+            None => {
+                let unquoted = &rest[i + 1..];
+                unquoted.split_at(unquoted.find(',').unwrap_or(unquoted.len()))
+            }
+        };
+        if name.eq_ignore_ascii_case("claims") {
+            let value = value.trim().trim_end_matches('=');
+            let bytes = STANDARD_NO_PAD
+                .decode(value)
+                .or_else(|_| URL_SAFE_NO_PAD.decode(value))
+                .ok()?;
+            return String::from_utf8(bytes).ok();
+        }
+        rest = next;
+    }
+    None
+}
+
+fn is_interaction_required(err: &JsValue) -> bool {
+    Reflect::get(err, &"name".into())
+        .ok()
+        .and_then(|v| v.as_string())
+        .is_some_and(|v| v == "InteractionRequiredAuthError")
+}
+
+fn interactive_request<'a, R>(
+    request: R,
+    claims: Option<&'a str>,
+    account: Option<&'a AccountInfo>,
+) -> R
+where
+    R: SetAuthorizationUrlRequest<'a>,
+{
+    let request = match claims {
+        Some(v) => request.set_claims(v),
+        None => request,
+    };
+    match account {
+        Some(v) => request.set_login_hint(v.username()),
+        None => request,
+    }
+}
+
+/// Sends requests with the token for the scopes from its [`ProtectedResourceMap`], and
+/// retries once when a 401 carries a claims challenge. The api must expose the
+/// `WWW-Authenticate` header to CORS requests for the challenge to be read.
+pub struct AuthenticatedClient {
+    auth: msal::PublicClientApplication,
+    token_manager: TokenManager,
+    resources: ProtectedResourceMap,
+    account: Option<AccountInfo>,
+    interaction_type: InteractionType,
+    authentication_scheme: AuthenticationScheme,
+    fetch: Option<Function>,
+}
+
+impl AuthenticatedClient {
+    pub fn new<T>(client_app: &T, resources: ProtectedResourceMap) -> Self
+    where
+        T: PublicClientApplication,
+    {
+        Self {
+            auth: JsValue::clone(client_app.auth()).unchecked_into(),
+            token_manager: TokenManager::new(client_app),
+            resources,
+            account: None,
+            interaction_type: InteractionType::Popup,
+            authentication_scheme: AuthenticationScheme::Bearer,
+            fetch: None,
+        }
+    }
+
+    /// Share the cached tokens with the rest of the app
+    pub fn set_token_manager(mut self, token_manager: TokenManager) -> Self {
+        self.token_manager = token_manager;
+        self
+    }
+
+    /// Defaults to the first signed in account
+    pub fn set_account(mut self, account: AccountInfo) -> Self {
+        self.account = Some(account);
+        self
+    }
+
+    /// Defaults to `Popup`, `Silent` never shows any ui. `Redirect` needs the `redirect` feature
+    pub fn set_interaction_type(mut self, interaction_type: InteractionType) -> Self {
+        self.interaction_type = interaction_type;
+        self
+    }
+
+    /// `Pop` binds each token to the method and url of the request
+    pub fn set_authentication_scheme(
+        mut self,
+        authentication_scheme: AuthenticationScheme,
+    ) -> Self {
+        self.authentication_scheme = authentication_scheme;
+        self
+    }
+
+    /// Replaces the global `fetch`, with a js function that takes a `Request` and returns a
+    /// `Promise<Response>`
+    pub fn set_fetch(mut self, fetch: Function) -> Self {
+        self.fetch = Some(fetch);
+        self
+    }
+
+    pub fn resources(&self) -> &ProtectedResourceMap {
+        &self.resources
+    }
+
+    pub fn token_manager(&self) -> &TokenManager {
+        &self.token_manager
+    }
+
+    pub async fn fetch(&self, request: &Request) -> Result<Response, ClientError> {
+        let scopes = match self.resources.scopes_for(&request.url(), &request.method()) {
+            Some(v) => v.to_vec(),
+            None => return self.send(request).await,
+        };
+        // Sending uses up the body, so keep a copy to retry with
+        let retry = request.clone()?;
+        let response = self.send_with_token(request, &scopes, None).await?;
+        if response.status() != UNAUTHORIZED {
+            return Ok(response);
+        }
+        let challenge = response.headers().get("WWW-Authenticate")?;
+        match challenge.as_deref().and_then(claims_challenge) {
+            Some(claims) => self.send_with_token(&retry, &scopes, Some(&claims)).await,
+            None => Ok(response),
+        }
+    }
+
+    pub async fn fetch_with_str(&self, url: &str) -> Result<Response, ClientError> {
+        self.fetch(&Request::new_with_str(url)?).await
+    }
+
+    async fn send_with_token(
+        &self,
+        request: &Request,
+        scopes: &[String],
+        claims: Option<&str>,
+    ) -> Result<Response, ClientError> {
+        let auth_result = self.acquire_token(request, scopes, claims).await?;
+        let scheme = match auth_result.token_type() {
+            v if v.eq_ignore_ascii_case(AuthenticationScheme::Pop.as_str()) => "PoP",
+            _ => "Bearer",
+        };
+        request.headers().set(
+            "Authorization",
+            &format!("{} {}", scheme, auth_result.access_token()),
+        )?;
+        self.send(request).await
+    }
+
+    async fn send(&self, request: &Request) -> Result<Response, ClientError> {
+        let promise = match &self.fetch {
+            Some(fetch) => Promise::resolve(&fetch.call1(&JsValue::NULL, request)?),
+            None => global_fetch(request),
+        };
+        Ok(JsFuture::from(promise).await?.unchecked_into())
+    }
+
+    async fn acquire_token(
+        &self,
+        request: &Request,
+        scopes: &[String],
+        claims: Option<&str>,
+    ) -> Result<AuthenticationResult, ClientError> {
+        let account = self.account.clone().or_else(|| {
+            self.auth
+                .get_all_accounts()
+                .and_then(|v| AccountInfo::from_array(v).into_iter().next())
+        });
+        let account = match account {
+            Some(v) => v,
+            None => return self.acquire_token_interactive(scopes, claims, None).await,
+        };
+
+        let pop = self.authentication_scheme == AuthenticationScheme::Pop;
+        let mut silent_request = SilentRequest::new(scopes, &account)
+            .set_authentication_scheme(self.authentication_scheme.clone());
+        if let Some(v) = claims {
+            silent_request = silent_request.set_claims(v);
+        }
+        if pop {
+            silent_request = silent_request
+                .set_resource_request_method(request.method())
+                .set_resource_request_uri(request.url());
+        }
+
+        if claims.is_some() {
+            // The shared token was rejected, so it mustn't be sent again
+            self.token_manager.remove(&TokenKey::from(&silent_request));
+        }
+        // PoP tokens are bound to the request, and a challenge needs a new token, so neither is shared
+        let result = if pop || claims.is_some() {
+            acquire_token_silent(&self.auth, &silent_request).await
+        } else {
+            self.token_manager
+                .acquire_token_silent(&silent_request)
+                .await
+        };
+        match result {
+            Err(e)
+                if self.interaction_type != InteractionType::Silent
+                    && is_interaction_required(&e) =>
+            {
+                let auth_result = self
+                    .acquire_token_interactive(scopes, claims, Some(&account))
+                    .await?;
+                // The interaction is only for consent, the token still has to be bound
+                if pop {
+                    Ok(acquire_token_silent(&self.auth, &silent_request).await?)
+                } else {
+                    Ok(auth_result)
+                }
+            }
+            result => Ok(result?),
+        }
+    }
+
+    async fn acquire_token_interactive(
+        &self,
+        scopes: &[String],
+        claims: Option<&str>,
+        account: Option<&AccountInfo>,
+    ) -> Result<AuthenticationResult, ClientError> {
+        match self.interaction_type {
+            InteractionType::Popup => {
+                let request =
+                    interactive_request(AuthorizationUrlRequest::new(scopes), claims, account);
+                let auth_result = self.auth.acquire_token_popup((&request).into()).await?;
+                Ok(auth_result.into())
+            }
+            #[cfg(feature = "redirect")]
+            InteractionType::Redirect => {
+                let request = interactive_request(
+                    crate::requests::RedirectRequest::new(scopes),
+                    claims,
+                    account,
+                );
+                self.auth.acquire_token_redirect((&request).into());
+                Err(ClientError::Redirecting)
+            }
+            #[cfg(not(feature = "redirect"))]
+            InteractionType::Redirect => Err(ClientError::RedirectNotEnabled),
+            InteractionType::Silent => Err(ClientError::NoAccount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAIMS: &str = r#"{"access_token":{"acrs":{"essential":true,"value":"c1"}}}"#;
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob("*", ""));
+        assert!(glob("/api/*", "/api/orders/1"));
+        assert!(glob("/api/*/items", "/api/orders/items"));
+        assert!(!glob("/api/*/items", "/api/orders/items/1"));
+        assert!(glob("*.contoso.com", "api.contoso.com"));
+        assert!(!glob("*.contoso.com", "contoso.com"));
+        assert!(glob("/a*b*c", "/aXbYbZc"));
+    }
+
+    #[test]
+    fn match_protected_resources() {
+        let resources = ProtectedResourceMap {
+            resources: Vec::new(),
+            origin: Some("http://localhost:8080".into()),
+        };
+        let resources = resources
+            .add("https://graph.microsoft.com", &["User.Read"])
+            .add("https://graph.microsoft.com/v1.0/me/*", &["Mail.Read"])
+            .add(
+                "https://graph.microsoft.com/v1.0/me/public/*",
+                &[] as &[&str],
+            )
+            .add_for_methods(
+                "https://*.contoso.com/orders/*",
+                &["post"],
+                &["Orders.Write"],
+            )
+            .add("https://*.contoso.com/orders/*", &["Orders.Read"])
+            .add("/api/*", &["api://local/.default"]);
+
+        let scopes = |url, method| resources.scopes_for(url, method).map(<[_]>::to_vec);
+        assert_eq!(
+            scopes("https://graph.microsoft.com/v1.0/users", "GET"),
+            Some(vec!["User.Read".to_string()])
+        );
+        assert_eq!(
+            scopes("https://GRAPH.microsoft.com/v1.0/me/messages?$top=1", "GET"),
+            Some(vec!["Mail.Read".to_string()])
+        );
+        assert_eq!(
+            scopes("https://graph.microsoft.com/v1.0/me/public/photo", "GET"),
+            None
+        );
+        assert_eq!(
+            scopes("https://eu.contoso.com/orders/1", "POST"),
+            Some(vec!["Orders.Write".to_string()])
+        );
+        assert_eq!(
+            scopes("https://eu.contoso.com/orders/1", "GET"),
+            Some(vec!["Orders.Read".to_string()])
+        );
+        assert_eq!(scopes("http://eu.contoso.com/orders/1", "GET"), None);
+        assert_eq!(
+            scopes("http://localhost:8080/api/values", "GET"),
+            Some(vec!["api://local/.default".to_string()])
+        );
+        assert_eq!(scopes("https://example.com/", "GET"), None);
+    }
+
+    #[test]
+    fn relative_patterns_stay_on_the_origin() {
+        let resources = ProtectedResourceMap {
+            resources: Vec::new(),
+            origin: Some("https://app.contoso.com".into()),
+        }
+        .add("/api/*", &["api://app/.default"]);
+        assert_eq!(
+            resources.scopes_for("https://app.contoso.com/api/orders", "GET"),
+            Some(&["api://app/.default".to_string()][..])
+        );
+        assert_eq!(
+            resources.scopes_for("https://evil.example.com/api/orders", "GET"),
+            None
+        );
+        assert_eq!(
+            resources.scopes_for("http://app.contoso.com/api/orders", "GET"),
+            None
+        );
+
+        // Without a page there is nothing to resolve against, so only relative urls match
+        let resources = ProtectedResourceMap {
+            resources: Vec::new(),
+            origin: None,
+        }
+        .add("/api/*", &["api://app/.default"]);
+        assert!(resources
+            .scopes_for("https://evil.example.com/api/orders", "GET")
+            .is_none());
+        assert!(resources.scopes_for("/api/orders", "GET").is_some());
+    }
+
+    #[test]
+    fn parse_claims_challenge() {
+        let encoded = STANDARD_NO_PAD.encode(CLAIMS);
+        let header = format!(
+            r#"Bearer realm="", authorization_uri="https://login.microsoftonline.com/common/oauth2/authorize", client_id="00000003-0000-0000-c000-000000000000", error="insufficient_claims", claims="{}==""#,
+            encoded
+        );
+        assert_eq!(claims_challenge(&header).as_deref(), Some(CLAIMS));
+        assert_eq!(
+            claims_challenge(&format!(
+                "Bearer error=insufficient_claims, claims={}",
+                encoded
+            ))
+            .as_deref(),
+            Some(CLAIMS)
+        );
+        assert_eq!(
+            claims_challenge(r#"Bearer realm="", error="invalid_token""#),
+            None
+        );
+        assert_eq!(claims_challenge(r#"Bearer xclaims="e30""#), None);
+        assert_eq!(claims_challenge(r#"Bearer claims="*""#), None);
+    }
+}
+
+#[cfg(all(test, feature = "popup"))]
+mod popup_tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::{
+        popup::PopupApp,
+        tests::*,
+        token_manager::tests::{response, seed},
+        BrowserAuthOptions, Configuration,
+    };
+    use std::{cell::Cell, rc::Rc};
+    use wasm_bindgen_test::*;
+    use web_sys::{Headers, ResponseInit};
+
+    const API: &str = "https://api.contoso.com/orders/1";
+
+    // Echoes the Authorization header, or replies with `status` if set
+    fn stand_in(calls: Rc<Cell<u32>>, status: Option<u16>) -> Function {
+        let fetch = Closure::<dyn Fn(Request) -> Promise>::new(move |request: Request| {
+            calls.set(calls.get() + 1);
+            let init = ResponseInit::new();
+            init.set_status(status.unwrap_or(200));
+            let body = request.headers().get("Authorization").unwrap();
+            let response =
+                Response::new_with_opt_str_and_init(body.as_deref().or(Some("none")), &init);
+            Promise::resolve(&JsValue::from(response.unwrap()))
+        });
+        fetch.into_js_value().unchecked_into()
+    }
+
+    // A 401 with a claims challenge, then echoes the Authorization header
+    fn challenging(calls: Rc<Cell<u32>>) -> Function {
+        let fetch = Closure::<dyn Fn(Request) -> Promise>::new(move |request: Request| {
+            calls.set(calls.get() + 1);
+            let init = ResponseInit::new();
+            if calls.get() == 1 {
+                let claims = STANDARD_NO_PAD
+                    .encode(r#"{"access_token":{"acrs":{"essential":true,"value":"c1"}}}"#);
+                let headers = Headers::new().unwrap();
+                headers
+                    .set(
+                        "WWW-Authenticate",
+                        &format!(r#"Bearer error="insufficient_claims", claims="{}""#, claims),
+                    )
+                    .unwrap();
+                init.set_status(UNAUTHORIZED);
+                init.set_headers(&headers);
+            }
+            let body = request.headers().get("Authorization").unwrap();
+            let response = Response::new_with_opt_str_and_init(body.as_deref(), &init);
+            Promise::resolve(&JsValue::from(response.unwrap()))
+        });
+        fetch.into_js_value().unchecked_into()
+    }
+
+    // Stands in for msal, there is no signed in account to get a token for
+    fn stub_acquire_token_silent(client: &AuthenticatedClient, access_token: &str) {
+        let response = response("challenged", 3600.0);
+        Reflect::set(&response, &"accessToken".into(), &access_token.into()).unwrap();
+        let silent =
+            Closure::<dyn Fn(JsValue) -> Promise>::new(move |_| Promise::resolve(&response));
+        Reflect::set(
+            &client.auth,
+            &"acquireTokenSilent".into(),
+            &silent.into_js_value(),
+        )
+        .unwrap();
+    }
+
+    fn client(fetch: Function) -> AuthenticatedClient {
+        let config = Configuration::new(BrowserAuthOptions::new(CLIENT_ID));
+        let resources =
+            ProtectedResourceMap::new().add("https://api.contoso.com/orders/*", &[SCOPE]);
+        let client = AuthenticatedClient::new(&PopupApp::new(config), resources)
+            .set_account(account())
+            .set_interaction_type(InteractionType::Silent)
+            .set_fetch(fetch);
+        let request = SilentRequest::new(&[SCOPE][..], account());
+        seed(
+            client.token_manager(),
+            TokenKey::from(&request),
+            response("cached", 3600.0).into(),
+        );
+        client
+    }
+
+    async fn text(response: Response) -> String {
+        JsFuture::from(response.text().unwrap())
+            .await
+            .unwrap()
+            .as_string()
+            .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn attaches_token() {
+        let calls = Rc::new(Cell::new(0));
+        let client = client(stand_in(calls.clone(), None));
+
+        let response = client.fetch_with_str(API).await.unwrap();
+        assert_eq!(text(response).await, "Bearer accessToken");
+
+        let response = client.fetch_with_str("https://example.com/").await.unwrap();
+        assert_eq!(text(response).await, "none");
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    async fn no_account_is_a_client_error() {
+        let config = Configuration::new(BrowserAuthOptions::new(CLIENT_ID));
+        let resources =
+            ProtectedResourceMap::new().add("https://api.contoso.com/orders/*", &[SCOPE]);
+        let client = AuthenticatedClient::new(&PopupApp::new(config), resources)
+            .set_interaction_type(InteractionType::Silent)
+            .set_fetch(stand_in(Rc::default(), None));
+
+        let err = client.fetch_with_str(API).await.unwrap_err();
+        assert_eq!(err, ClientError::NoAccount);
+        assert!(JsValue::from(err).is_instance_of::<js_sys::Error>());
+    }
+
+    #[wasm_bindgen_test]
+    async fn returns_unauthorized_without_challenge() {
+        let calls = Rc::new(Cell::new(0));
+        let client = client(stand_in(calls.clone(), Some(UNAUTHORIZED)));
+
+        let response = client.fetch_with_str(API).await.unwrap();
+        assert_eq!(response.status(), UNAUTHORIZED);
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn retries_claims_challenge() {
+        let calls = Rc::new(Cell::new(0));
+        let client = client(challenging(calls.clone()));
+        stub_acquire_token_silent(&client, "challengedToken");
+
+        let response = client.fetch_with_str(API).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(text(response).await, "Bearer challengedToken");
+        assert_eq!(calls.get(), 2);
+
+        // The rejected token isn't kept for the next request
+        let key = TokenKey::from(&SilentRequest::new(&[SCOPE][..], account()));
+        assert!(client.token_manager().cached(&key).is_none());
+    }
+}
@@ -0,0 +1,489 @@
+//! Encrypts the tokens msal keeps in `localStorage` or `sessionStorage`. msal reads the cache
+//! synchronously but WebCrypto is async, so the entries are decrypted into memory once when
+//! installed and each token msal writes is encrypted in the background. The AES-GCM key is
+//! non-extractable and kept in IndexedDB, if it is lost or replaced the tokens it encrypted
+//! can't be read and are removed, as if the cache was empty.
+//!
+//! Accounts and the other entries msal stores are left as they are, only the id, access and
+//! refresh tokens are encrypted.
+
+use crate::{cache, msal, PublicClientApplication};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    AesGcmParams, AesKeyGenParams, Crypto, CryptoKey, Event, IdbDatabase, IdbObjectStore,
+    IdbOpenDbRequest, IdbRequest, IdbTransactionMode, Storage, StorageEvent, SubtleCrypto,
+};
+
+pub const DEFAULT_DATABASE_NAME: &str = "msal.encryption";
+const STORE: &str = "keys";
+const KEY_ID: &str = "cache";
+/// Marks an encrypted value, the version allows the format to change
+const PREFIX: &str = "msal.enc.v1:";
+const ALGORITHM: &str = "AES-GCM";
+const IV_LENGTH: usize = 12;
+
+/// Only these hold secrets, the same values as msal's `CredentialType`
+fn is_token_entry(value: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(value)
+        .ok()
+        .and_then(|v| v.get("credentialType")?.as_str().map(str::to_owned))
+        .is_some_and(|v| {
+            matches!(
+                v.as_str(),
+                "IdToken" | "AccessToken" | "AccessToken_With_AuthScheme" | "RefreshToken"
+            )
+        })
+}
+
+fn encode(iv: &[u8], ciphertext: &[u8]) -> String {
+    format!(
+        "{}{}",
+        PREFIX,
+        URL_SAFE_NO_PAD.encode([iv, ciphertext].concat())
+    )
+}
+
+/// The iv and ciphertext, `None` if not encrypted or malformed
+fn decode(value: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut bytes = URL_SAFE_NO_PAD.decode(value.strip_prefix(PREFIX)?).ok()?;
+    if bytes.len() <= IV_LENGTH {
+        return None;
+    }
+    let ciphertext = bytes.split_off(IV_LENGTH);
+    Some((bytes, ciphertext))
+}
+
+/// Resolves with the result of an IndexedDB request
+async fn idb_result(request: &IdbRequest) -> Result<JsValue, JsValue> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    JsFuture::from(promise).await?;
+    request.result()
+}
+
+struct Cipher {
+    crypto: Crypto,
+    key: CryptoKey,
+}
+
+impl Cipher {
+    async fn load(crypto: Crypto, database_name: &str) -> Result<Self, JsValue> {
+        let factory = web_sys::window()
+            .and_then(|v| v.indexed_db().ok().flatten())
+            .ok_or_else(|| JsValue::from("IndexedDB is not available"))?;
+        let open: IdbOpenDbRequest = factory.open_with_u32(database_name, 1)?;
+        let upgrade = {
+            let open = open.clone();
+            Closure::once_into_js(move |_: Event| {
+                if let Ok(db) = open.result() {
+                    let _ = db
+                        .unchecked_into::<IdbDatabase>()
+                        .create_object_store(STORE);
+                }
+            })
+        };
+        open.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+        let db: IdbDatabase = idb_result(&open).await?.unchecked_into();
+
+        let store = |mode| -> Result<IdbObjectStore, JsValue> {
+            db.transaction_with_str_and_mode(STORE, mode)?
+                .object_store(STORE)
+        };
+        let key = idb_result(&store(IdbTransactionMode::Readonly)?.get(&KEY_ID.into())?).await?;
+        let key = match key.dyn_into::<CryptoKey>() {
+            Ok(key) => key,
+            Err(_) => {
+                let params = AesKeyGenParams::new(ALGORITHM, 256);
+                let usages = Array::of2(&"encrypt".into(), &"decrypt".into());
+                let promise = crypto
+                    .subtle()
+                    .generate_key_with_object(&params, false, &usages)?;
+                let key: CryptoKey = JsFuture::from(promise).await?.unchecked_into();
+                // `add` fails if another tab stored a key first, then use theirs
+                let added = idb_result(
+                    &store(IdbTransactionMode::Readwrite)?.add_with_key(&key, &KEY_ID.into())?,
+                )
+                .await;
+                match added {
+                    Ok(_) => key,
+                    Err(_) => {
+                        idb_result(&store(IdbTransactionMode::Readonly)?.get(&KEY_ID.into())?)
+                            .await?
+                            .dyn_into()?
+                    }
+                }
+            }
+        };
+        db.close();
+        Ok(Self { crypto, key })
+    }
+
+    fn subtle(&self) -> SubtleCrypto {
+        self.crypto.subtle()
+    }
+
+    // The storage key is the additional data, so a value can't be moved to another key
+    fn params(&self, iv: &[u8], storage_key: &str) -> AesGcmParams {
+        let params = AesGcmParams::new(ALGORITHM, &Uint8Array::from(iv));
+        params.set_additional_data_u8_array(&Uint8Array::from(storage_key.as_bytes()));
+        params
+    }
+
+    async fn encrypt(&self, storage_key: &str, value: &str) -> Result<String, JsValue> {
+        let mut iv = [0; IV_LENGTH];
+        self.crypto.get_random_values_with_u8_array(&mut iv)?;
+        let promise = self.subtle().encrypt_with_object_and_u8_array(
+            &self.params(&iv, storage_key),
+            &self.key,
+            value.as_bytes(),
+        )?;
+        let ciphertext = Uint8Array::new(&JsFuture::from(promise).await?).to_vec();
+        Ok(encode(&iv, &ciphertext))
+    }
+
+    async fn decrypt(&self, storage_key: &str, value: &str) -> Result<String, JsValue> {
+        let (iv, ciphertext) = decode(value).ok_or_else(|| JsValue::from("malformed value"))?;
+        let promise = self.subtle().decrypt_with_object_and_u8_array(
+            &self.params(&iv, storage_key),
+            &self.key,
+            &ciphertext,
+        )?;
+        let plaintext = Uint8Array::new(&JsFuture::from(promise).await?).to_vec();
+        String::from_utf8(plaintext).map_err(|e| e.to_string().into())
+    }
+}
+
+/// Stands in for the `Storage` msal uses. The decrypted entries are its own enumerable
+/// properties, since msal lists the keys with `Object.keys` and checks them with
+/// `hasOwnProperty`.
+struct EncryptedStorage {
+    cipher: Cipher,
+    storage: Storage,
+    entries: Object,
+    // Stops an older write that finishes encrypting last from replacing a newer one
+    versions: RefCell<HashMap<String, u64>>,
+}
+
+impl EncryptedStorage {
+    fn get_item(&self, key: &str) -> JsValue {
+        match Reflect::get_own_property_descriptor(&self.entries, &key.into()) {
+            Ok(v) if !v.is_undefined() => {
+                Reflect::get(&self.entries, &key.into()).unwrap_or(JsValue::NULL)
+            }
+            _ => JsValue::NULL,
+        }
+    }
+
+    fn set_entry(&self, key: &str, value: &str) {
+        let _ = Reflect::set(&self.entries, &key.into(), &value.into());
+    }
+
+    fn remove_entry(&self, key: &str) {
+        let _ = Reflect::delete_property(&self.entries, &key.into());
+    }
+
+    fn next_version(&self, key: &str) -> u64 {
+        let mut versions = self.versions.borrow_mut();
+        let version = versions.entry(key.to_owned()).or_default();
+        *version += 1;
+        *version
+    }
+
+    fn set_item(self: &Rc<Self>, key: String, value: String) {
+        self.set_entry(&key, &value);
+        let version = self.next_version(&key);
+        if !is_token_entry(&value) {
+            let _ = self.storage.set_item(&key, &value);
+            return;
+        }
+        let this = self.clone();
+        spawn_local(async move {
+            if let Ok(encrypted) = this.cipher.encrypt(&key, &value).await {
+                if this.versions.borrow().get(&key) == Some(&version) {
+                    let _ = this.storage.set_item(&key, &encrypted);
+                }
+            }
+        });
+    }
+
+    fn remove_item(&self, key: &str) {
+        self.remove_entry(key);
+        self.next_version(key);
+        let _ = self.storage.remove_item(key);
+    }
+
+    /// Reads a stored value, dropping any that can't be decrypted
+    async fn load(&self, key: &str, value: &str) -> Result<(), JsValue> {
+        if !value.starts_with(PREFIX) {
+            self.set_entry(key, value);
+            // Written before encryption was turned on
+            if is_token_entry(value) {
+                let encrypted = self.cipher.encrypt(key, value).await?;
+                self.storage.set_item(key, &encrypted)?;
+            }
+            return Ok(());
+        }
+        match self.cipher.decrypt(key, value).await {
+            Ok(v) => self.set_entry(key, &v),
+            Err(_) => {
+                self.remove_entry(key);
+                self.storage.remove_item(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A value another tab wrote. One that can't be decrypted is only dropped from memory,
+    /// it isn't this tab's to remove from storage
+    async fn reload(&self, key: &str, value: &str, version: u64) {
+        let value = if value.starts_with(PREFIX) {
+            self.cipher.decrypt(key, value).await.ok()
+        } else {
+            Some(value.to_owned())
+        };
+        // A newer change may have come in while decrypting
+        if self.versions.borrow().get(key) != Some(&version) {
+            return;
+        }
+        match value {
+            Some(v) => self.set_entry(key, &v),
+            None => self.remove_entry(key),
+        }
+    }
+
+    /// Changes written by other tabs, only fired for `localStorage`
+    fn on_storage(self: &Rc<Self>, event: StorageEvent) {
+        if event.storage_area().as_ref() != Some(&self.storage) {
+            return;
+        }
+        let key = match event.key() {
+            Some(v) => v,
+            // The storage was cleared
+            None => {
+                for key in Object::keys(&self.entries)
+                    .iter()
+                    .filter_map(|v| v.as_string())
+                {
+                    self.remove_entry(&key)
+                }
+                return;
+            }
+        };
+        let version = self.next_version(&key);
+        match event.new_value() {
+            Some(value) => {
+                let this = self.clone();
+                spawn_local(async move { this.reload(&key, &value, version).await });
+            }
+            None => self.remove_entry(&key),
+        }
+    }
+}
+
+fn define_method(target: &Object, name: &str, method: JsValue) {
+    let descriptor = Object::new();
+    let _ = Reflect::set(&descriptor, &"value".into(), &method);
+    Object::define_property(target, &name.into(), &descriptor);
+}
+
+/// Encrypts msal's token cache, opt in with the `encryption` feature
+#[derive(Clone, Debug)]
+pub struct EncryptedCache {
+    database_name: String,
+}
+
+impl Default for EncryptedCache {
+    fn default() -> Self {
+        Self {
+            database_name: DEFAULT_DATABASE_NAME.to_owned(),
+        }
+    }
+}
+
+impl EncryptedCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The IndexedDB database that holds the key
+    pub fn set_database_name(mut self, database_name: &str) -> Self {
+        self.database_name = database_name.to_owned();
+        self
+    }
+
+    /// Must be awaited straight after creating the app, before anything reads the cache.
+    /// Does nothing when the cache is in `memoryStorage`.
+    pub async fn install<T>(&self, client_app: &T) -> Result<(), JsValue>
+    where
+        T: PublicClientApplication,
+    {
+        install(client_app.auth(), &self.database_name).await
+    }
+
+    /// Removes the key, the encrypted tokens can't be read by the next page load
+    pub async fn delete_key(&self) -> Result<(), JsValue> {
+        let factory = web_sys::window()
+            .and_then(|v| v.indexed_db().ok().flatten())
+            .ok_or_else(|| JsValue::from("IndexedDB is not available"))?;
+        let request = factory.delete_database(&self.database_name)?;
+        idb_result(&request).await?;
+        Ok(())
+    }
+}
+
+async fn install(
+    client_app: &msal::PublicClientApplication,
+    database_name: &str,
+) -> Result<(), JsValue> {
+    let location = cache::cache_location(client_app);
+    if location != "localStorage" && location != "sessionStorage" {
+        return Ok(());
+    }
+    let storage: Storage = match cache::window_storage(client_app).map(JsCast::dyn_into) {
+        Some(Ok(v)) => v,
+        // Already installed
+        Some(Err(_)) => return Ok(()),
+        // Don't silently leave the tokens in plain text
+        None => return Err("msal's cache storage was not found".into()),
+    };
+    let window = web_sys::window().ok_or_else(|| JsValue::from("no window"))?;
+    let cipher = Cipher::load(window.crypto()?, database_name).await?;
+
+    let this = Rc::new(EncryptedStorage {
+        cipher,
+        storage: storage.clone(),
+        entries: Object::new(),
+        versions: Default::default(),
+    });
+    for key in Object::keys(&storage).iter().filter_map(|v| v.as_string()) {
+        if let Some(value) = storage.get_item(&key)? {
+            this.load(&key, &value).await?;
+        }
+    }
+
+    let methods = [
+        ("getItem", {
+            let this = this.clone();
+            Closure::<dyn Fn(String) -> JsValue>::new(move |key: String| this.get_item(&key))
+                .into_js_value()
+        }),
+        ("setItem", {
+            let this = this.clone();
+            Closure::<dyn Fn(String, String)>::new(move |key, value| this.set_item(key, value))
+                .into_js_value()
+        }),
+        ("removeItem", {
+            let this = this.clone();
+            Closure::<dyn Fn(String)>::new(move |key: String| this.remove_item(&key))
+                .into_js_value()
+        }),
+    ];
+    for (name, method) in methods {
+        define_method(&this.entries, name, method)
+    }
+    let on_storage = {
+        let this = this.clone();
+        Closure::<dyn Fn(StorageEvent)>::new(move |event| this.on_storage(event)).into_js_value()
+    };
+    window.add_event_listener_with_callback("storage", on_storage.unchecked_ref::<Function>())?;
+
+    let browser_storage = Reflect::get(client_app, &"browserStorage".into())?;
+    let browser_storage = Reflect::get(&browser_storage, &"browserStorage".into())?;
+    Reflect::set(&browser_storage, &"windowStorage".into(), &this.entries)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_entries() {
+        assert!(is_token_entry(
+            r#"{"credentialType":"RefreshToken","secret":"rt"}"#
+        ));
+        assert!(is_token_entry(
+            r#"{"credentialType":"AccessToken_With_AuthScheme"}"#
+        ));
+        assert!(!is_token_entry(
+            r#"{"homeAccountId":"uid.utid","username":"user"}"#
+        ));
+        assert!(!is_token_entry(r#"{"credentialType":"Unknown"}"#));
+        assert!(!is_token_entry("uid.utid"));
+    }
+
+    #[test]
+    fn encoding_round_trip() {
+        let iv = [7; IV_LENGTH];
+        let encoded = encode(&iv, b"ciphertext");
+        assert!(encoded.starts_with(PREFIX));
+        assert_eq!(
+            decode(&encoded),
+            Some((iv.to_vec(), b"ciphertext".to_vec()))
+        );
+
+        assert_eq!(decode("plain text"), None);
+        assert_eq!(decode(&format!("{}not base64!", PREFIX)), None);
+        assert_eq!(decode(&encode(&iv, b"")), None);
+    }
+}
+
+#[cfg(all(test, feature = "popup"))]
+mod popup_tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::{
+        msal::Msal, popup::PopupApp, tests::*, BrowserAuthOptions, CacheLocation, CacheOptions,
+        Configuration,
+    };
+    use wasm_bindgen_test::*;
+
+    const TOKEN_KEY: &str = "uid.utid-login.microsoftonline.com-refreshtoken-client_id----";
+    const TOKEN: &str = r#"{"credentialType":"RefreshToken","secret":"refreshToken"}"#;
+
+    fn client_app() -> PopupApp {
+        let config = Configuration::new(BrowserAuthOptions::new(CLIENT_ID))
+            .set_cache(CacheOptions::new().set_cache_location(CacheLocation::Local));
+        PopupApp::new(config)
+    }
+
+    fn local_storage() -> Storage {
+        web_sys::window().unwrap().local_storage().unwrap().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn encrypts_tokens() {
+        let storage = local_storage();
+        storage.set_item(TOKEN_KEY, TOKEN).unwrap();
+        let client_app = client_app();
+        let encrypted_cache = EncryptedCache::new().set_database_name("msal.encryption.test");
+        encrypted_cache.install(&client_app).await.unwrap();
+
+        // Migrated, and msal still reads the plain text
+        let stored = storage.get_item(TOKEN_KEY).unwrap().unwrap();
+        assert!(stored.starts_with(PREFIX));
+        let entries = cache::window_storage(client_app.auth()).unwrap();
+        assert_eq!(Reflect::get(&entries, &TOKEN_KEY.into()).unwrap(), TOKEN);
+        let snapshot = client_app.cache_snapshot().unwrap();
+        assert_eq!(snapshot.refresh_tokens().len(), 1);
+
+        // A new key can't read it, so the token is dropped
+        encrypted_cache.delete_key().await.unwrap();
+        let client_app = self::client_app();
+        encrypted_cache.install(&client_app).await.unwrap();
+        assert_eq!(storage.get_item(TOKEN_KEY).unwrap(), None);
+        assert!(client_app
+            .cache_snapshot()
+            .unwrap()
+            .refresh_tokens()
+            .is_empty());
+        encrypted_cache.delete_key().await.unwrap();
+    }
+}
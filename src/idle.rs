@@ -0,0 +1,508 @@
+//! Signs the user out after a period without activity, warning them first. The last
+//! activity is shared between tabs, so working in one tab keeps the others signed in.
+
+use crate::{
+    logout_local, msal, requests::EndSessionRequest, token_manager::TokenManager,
+    PublicClientApplication,
+};
+use js_sys::Date;
+use std::{cell::Cell, rc::Rc, time::Duration};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{BroadcastChannel, Event, EventTarget, MessageEvent, StorageEvent, Window};
+
+/// The events that count as activity, listened to on the `window` in the capture phase
+pub const DEFAULT_ACTIVITY_EVENTS: [&str; 7] = [
+    "mousemove",
+    "mousedown",
+    "keydown",
+    "touchstart",
+    "wheel",
+    "scroll",
+    "visibilitychange",
+];
+/// The `localStorage` key or `BroadcastChannel` name
+pub const DEFAULT_STORAGE_KEY: &str = "msal.idle.lastActivity";
+const DEFAULT_WARNING: Duration = Duration::from_secs(60);
+/// Activity is shared at most this often, `mousemove` fires many times a second
+const SHARE_INTERVAL_MILLIS: f64 = 1000.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdleEvent {
+    /// The time left before the user is signed out
+    Warning(Duration),
+    /// Activity after a warning, in this or another tab
+    Active,
+    /// Sent before the logout
+    Timeout,
+    /// The logout after a timeout was rejected by msal, e.g. with `interaction_in_progress`
+    /// while a popup is open. The monitor has stopped so the app has to sign the user out.
+    LogoutFailed(JsValue),
+}
+
+/// How the last activity is shared between tabs of the same origin
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ActivitySync {
+    #[default]
+    LocalStorage,
+    BroadcastChannel,
+    /// Each tab times out on its own
+    None,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum IdleLogout {
+    /// Clears the cache, the user stays signed in to their identity provider
+    Local(EndSessionRequest<'static>),
+    /// Navigates to the end session endpoint
+    Server(EndSessionRequest<'static>),
+}
+
+impl Default for IdleLogout {
+    fn default() -> Self {
+        Self::Local(EndSessionRequest::default())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Active,
+    Warning,
+    TimedOut,
+}
+
+/// The phase after being idle for `idle`, and the time until it changes
+fn phase(idle: Duration, timeout: Duration, warning: Duration) -> (Phase, Duration) {
+    let warn_at = timeout.saturating_sub(warning);
+    if idle >= timeout {
+        (Phase::TimedOut, Duration::ZERO)
+    } else if idle >= warn_at {
+        (Phase::Warning, timeout - idle)
+    } else {
+        (Phase::Active, warn_at - idle)
+    }
+}
+
+type Listener = Rc<dyn Fn(&IdleEvent)>;
+/// The event, its target and the listener that receives activity from other tabs
+type SyncListener = (&'static str, EventTarget, Closure<dyn Fn(Event)>);
+
+/// Configures the monitor, nothing is listened to until [`start`](Self::start)
+pub struct IdleMonitor {
+    auth: msal::PublicClientApplication,
+    timeout: Duration,
+    warning: Duration,
+    events: Vec<String>,
+    sync: ActivitySync,
+    storage_key: String,
+    logout: IdleLogout,
+    token_manager: Option<TokenManager>,
+    listeners: Vec<Listener>,
+}
+
+impl IdleMonitor {
+    /// Signs out after `timeout` without activity, with a warning a minute before
+    pub fn new<T>(client_app: &T, timeout: Duration) -> Self
+    where
+        T: PublicClientApplication,
+    {
+        Self {
+            auth: JsValue::clone(client_app.auth()).unchecked_into(),
+            timeout,
+            warning: DEFAULT_WARNING,
+            events: DEFAULT_ACTIVITY_EVENTS
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            sync: ActivitySync::default(),
+            storage_key: DEFAULT_STORAGE_KEY.to_owned(),
+            logout: IdleLogout::default(),
+            token_manager: None,
+            listeners: Vec::new(),
+        }
+    }
+
+    /// How long before the timeout to warn, zero for no warning
+    pub fn set_warning(mut self, warning: Duration) -> Self {
+        self.warning = warning;
+        self
+    }
+
+    pub fn set_activity_events(mut self, events: &[&str]) -> Self {
+        self.events = events.iter().map(|v| v.to_string()).collect();
+        self
+    }
+
+    pub fn set_activity_sync(mut self, sync: ActivitySync) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Use a different key for each app on the same origin
+    pub fn set_storage_key(mut self, storage_key: &str) -> Self {
+        self.storage_key = storage_key.to_owned();
+        self
+    }
+
+    /// Defaults to a local logout of all accounts
+    pub fn set_logout(mut self, logout: IdleLogout) -> Self {
+        self.logout = logout;
+        self
+    }
+
+    /// Cleared on timeout, otherwise its tokens outlive the logout
+    pub fn set_token_manager(mut self, token_manager: TokenManager) -> Self {
+        self.token_manager = Some(token_manager);
+        self
+    }
+
+    pub fn on_event<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&IdleEvent) + 'static,
+    {
+        self.listeners.push(Rc::new(callback));
+        self
+    }
+
+    /// Starts listening, stopped when the handle is dropped
+    pub fn start(self) -> Result<IdleHandle, JsValue> {
+        let window = web_sys::window().ok_or_else(|| JsValue::from("no window"))?;
+        let channel = match self.sync {
+            ActivitySync::BroadcastChannel => Some(BroadcastChannel::new(&self.storage_key)?),
+            _ => None,
+        };
+        let now = Date::now();
+        let state = Rc::new(State {
+            window: window.clone(),
+            channel: channel.clone(),
+            last_activity: Cell::new(now),
+            last_shared: Cell::new(f64::NEG_INFINITY),
+            phase: Cell::new(Phase::Active),
+            running: Cell::new(true),
+            monitor: self,
+        });
+        state.share(now);
+
+        let activity = {
+            let state = state.clone();
+            Closure::<dyn Fn()>::new(move || state.activity(Date::now(), true))
+        };
+        for event in &state.monitor.events {
+            window.add_event_listener_with_callback_and_bool(
+                event,
+                activity.as_ref().unchecked_ref(),
+                true,
+            )?;
+        }
+
+        let sync = match (&state.monitor.sync, &channel) {
+            (ActivitySync::LocalStorage, _) => {
+                let state = state.clone();
+                let closure = Closure::<dyn Fn(Event)>::new(move |event: Event| {
+                    let event: StorageEvent = event.unchecked_into();
+                    if event.key().as_deref() == Some(&state.monitor.storage_key) {
+                        if let Some(at) = event.new_value().and_then(|v| v.parse().ok()) {
+                            state.activity(at, false)
+                        }
+                    }
+                });
+                window.add_event_listener_with_callback(
+                    "storage",
+                    closure.as_ref().unchecked_ref(),
+                )?;
+                Some(("storage", window.clone().unchecked_into(), closure))
+            }
+            (ActivitySync::BroadcastChannel, Some(channel)) => {
+                let state = state.clone();
+                let closure = Closure::<dyn Fn(Event)>::new(move |event: Event| {
+                    if let Some(at) = event.unchecked_into::<MessageEvent>().data().as_f64() {
+                        state.activity(at, false)
+                    }
+                });
+                channel.add_event_listener_with_callback(
+                    "message",
+                    closure.as_ref().unchecked_ref(),
+                )?;
+                Some(("message", channel.clone().unchecked_into(), closure))
+            }
+            _ => None,
+        };
+
+        spawn_local(state.clone().run());
+        Ok(IdleHandle {
+            state,
+            activity,
+            sync,
+        })
+    }
+}
+
+struct State {
+    monitor: IdleMonitor,
+    window: Window,
+    channel: Option<BroadcastChannel>,
+    // Milliseconds since the unix epoch
+    last_activity: Cell<f64>,
+    last_shared: Cell<f64>,
+    phase: Cell<Phase>,
+    running: Cell<bool>,
+}
+
+impl State {
+    fn activity(&self, at: f64, local: bool) {
+        if !self.running.get() || at <= self.last_activity.get() {
+            return;
+        }
+        self.last_activity.set(at);
+        if local {
+            self.share(at)
+        }
+        if self.phase.replace(Phase::Active) == Phase::Warning {
+            self.emit(&IdleEvent::Active)
+        }
+    }
+
+    fn share(&self, at: f64) {
+        if at - self.last_shared.get() < SHARE_INTERVAL_MILLIS {
+            return;
+        }
+        self.last_shared.set(at);
+        // Sharing is best effort, e.g. storage is unavailable in some private modes
+        match (&self.monitor.sync, &self.channel) {
+            (ActivitySync::LocalStorage, _) => {
+                if let Ok(Some(storage)) = self.window.local_storage() {
+                    let _ = storage.set_item(&self.monitor.storage_key, &at.to_string());
+                }
+            }
+            (ActivitySync::BroadcastChannel, Some(channel)) => {
+                let _ = channel.post_message(&at.into());
+            }
+            _ => {}
+        }
+    }
+
+    fn emit(&self, event: &IdleEvent) {
+        // Cloned so a listener can stop the monitor
+        let listeners = self.monitor.listeners.clone();
+        for listener in listeners {
+            listener(event)
+        }
+    }
+
+    async fn run(self: Rc<Self>) {
+        while self.running.get() {
+            let idle = Duration::from_secs_f64(
+                ((Date::now() - self.last_activity.get()) / 1000.0).max(0.0),
+            );
+            let (phase, next) = phase(idle, self.monitor.timeout, self.monitor.warning);
+            match (self.phase.replace(phase), phase) {
+                (_, Phase::TimedOut) => {
+                    self.running.set(false);
+                    self.emit(&IdleEvent::Timeout);
+                    if let Some(token_manager) = &self.monitor.token_manager {
+                        token_manager.clear();
+                    }
+                    if let Err(e) = self.logout().await {
+                        self.emit(&IdleEvent::LogoutFailed(e))
+                    }
+                }
+                (Phase::Active, Phase::Warning) => self.emit(&IdleEvent::Warning(next)),
+                _ => {}
+            }
+            crate::time::sleep(next).await;
+        }
+    }
+
+    async fn logout(&self) -> Result<(), JsValue> {
+        match &self.monitor.logout {
+            IdleLogout::Local(request) => logout_local(&self.monitor.auth, request.clone()).await,
+            IdleLogout::Server(request) => self
+                .monitor
+                .auth
+                .logout_redirect(request.clone().into())
+                .await
+                .map(|_| ()),
+        }
+    }
+}
+
+/// A running [`IdleMonitor`], keep it alive for as long as the user should be monitored
+pub struct IdleHandle {
+    state: Rc<State>,
+    activity: Closure<dyn Fn()>,
+    sync: Option<SyncListener>,
+}
+
+impl IdleHandle {
+    /// Milliseconds since the unix epoch of the last activity in any tab
+    pub fn last_activity(&self) -> f64 {
+        self.state.last_activity.get()
+    }
+
+    /// Counts as activity, e.g. for work that doesn't fire DOM events
+    pub fn record_activity(&self) {
+        self.state.activity(Date::now(), true)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.running.get()
+    }
+
+    /// Removes the listeners, no more events are sent
+    pub fn stop(&self) {
+        self.state.running.set(false);
+        let window = &self.state.window;
+        for event in &self.state.monitor.events {
+            let _ = window.remove_event_listener_with_callback_and_bool(
+                event,
+                self.activity.as_ref().unchecked_ref(),
+                true,
+            );
+        }
+        if let Some((event, target, closure)) = &self.sync {
+            let _ =
+                target.remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+        }
+        if let Some(channel) = &self.state.channel {
+            channel.close()
+        }
+    }
+}
+
+impl Drop for IdleHandle {
+    fn drop(&mut self) {
+        self.stop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn idle_phases() {
+        let timeout = 15 * MINUTE;
+        assert_eq!(
+            phase(Duration::ZERO, timeout, MINUTE),
+            (Phase::Active, 14 * MINUTE)
+        );
+        assert_eq!(
+            phase(14 * MINUTE, timeout, MINUTE),
+            (Phase::Warning, MINUTE)
+        );
+        assert_eq!(
+            phase(15 * MINUTE, timeout, MINUTE),
+            (Phase::TimedOut, Duration::ZERO)
+        );
+        // No warning
+        assert_eq!(
+            phase(14 * MINUTE, timeout, Duration::ZERO),
+            (Phase::Active, MINUTE)
+        );
+        // A warning longer than the timeout warns straight away
+        assert_eq!(
+            phase(Duration::ZERO, MINUTE, timeout),
+            (Phase::Warning, MINUTE)
+        );
+    }
+
+    #[test]
+    fn idle_transitions() {
+        let (timeout, warning) = (3 * MINUTE, 2 * MINUTE);
+        // Checked again when each phase is due to end
+        let (active, next) = phase(Duration::ZERO, timeout, warning);
+        assert_eq!((active, next), (Phase::Active, MINUTE));
+        let (warned, next) = phase(next, timeout, warning);
+        assert_eq!((warned, next), (Phase::Warning, 2 * MINUTE));
+        assert_eq!(
+            phase(MINUTE + next, timeout, warning),
+            (Phase::TimedOut, Duration::ZERO)
+        );
+        // Activity part way through the warning starts the count over
+        let (_, next) = phase(Duration::ZERO, timeout, warning);
+        assert_eq!(
+            phase(next + MINUTE / 2, timeout, warning),
+            (Phase::Warning, MINUTE + MINUTE / 2)
+        );
+        assert_eq!(
+            phase(timeout + MINUTE, timeout, warning),
+            (Phase::TimedOut, Duration::ZERO)
+        );
+    }
+}
+
+#[cfg(all(test, feature = "popup"))]
+mod popup_tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::{
+        popup::PopupApp,
+        requests::SilentRequest,
+        tests::*,
+        token_manager::{
+            tests::{response, seed},
+            TokenKey,
+        },
+        BrowserAuthOptions, Configuration,
+    };
+    use std::cell::RefCell;
+    use wasm_bindgen_test::*;
+
+    fn client_app() -> PopupApp {
+        PopupApp::new(Configuration::new(BrowserAuthOptions::new(CLIENT_ID)))
+    }
+
+    fn monitor(client_app: &PopupApp, events: Rc<RefCell<Vec<IdleEvent>>>) -> IdleMonitor {
+        // Generous so a busy browser doesn't miss a phase
+        IdleMonitor::new(client_app, Duration::from_secs(3))
+            .set_warning(Duration::from_secs(2))
+            .set_storage_key("msal.idle.test")
+            .on_event(move |event| events.borrow_mut().push(event.clone()))
+    }
+
+    #[wasm_bindgen_test]
+    async fn warns_then_times_out() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let client_app = client_app();
+        let token_manager = TokenManager::new(&client_app);
+        let key = TokenKey::from(&SilentRequest::new(&["User.Read"][..], account()));
+        seed(
+            &token_manager,
+            key.clone(),
+            response("cached", 3600.0).into(),
+        );
+        let handle = monitor(&client_app, events.clone())
+            .set_token_manager(token_manager.clone())
+            .start()
+            .unwrap();
+
+        // Warns after a second, times out after three
+        crate::time::sleep(Duration::from_millis(1500)).await;
+        assert!(matches!(events.borrow()[..], [IdleEvent::Warning(_)]));
+        let event = Event::new("keydown").unwrap();
+        web_sys::window().unwrap().dispatch_event(&event).unwrap();
+        assert_eq!(events.borrow().last(), Some(&IdleEvent::Active));
+
+        crate::time::sleep(Duration::from_secs(4)).await;
+        assert_eq!(events.borrow().last(), Some(&IdleEvent::Timeout));
+        assert_eq!(events.borrow().len(), 4);
+        assert!(!handle.is_running());
+        assert!(token_manager.cached(&key).is_none());
+    }
+
+    #[wasm_bindgen_test]
+    async fn shares_activity() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let handle = monitor(&client_app(), events.clone()).start().unwrap();
+        let storage = web_sys::window().unwrap().local_storage().unwrap().unwrap();
+        let shared = storage.get_item("msal.idle.test").unwrap().unwrap();
+        assert_eq!(shared.parse::<f64>().unwrap(), handle.last_activity());
+
+        handle.stop();
+        crate::time::sleep(Duration::from_millis(1500)).await;
+        assert!(events.borrow().is_empty());
+    }
+}
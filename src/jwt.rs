@@ -0,0 +1,227 @@
+//! Decoding of the compact JWS serialization, without any signature validation
+//! https://tools.ietf.org/html/rfc7515#section-7.1
+
+use crate::TokenClaims;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::{Map, Value};
+use std::{fmt, str::FromStr};
+use wasm_bindgen::JsValue;
+
+pub type JsonObject = Map<String, Value>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JwtPart {
+    Header,
+    Payload,
+    Signature,
+}
+
+impl fmt::Display for JwtPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header => write!(f, "header"),
+            Self::Payload => write!(f, "payload"),
+            Self::Signature => write!(f, "signature"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JwtError {
+    /// A JWS has 3 parts, a JWE has 5 and can't be decoded
+    PartCount(usize),
+    Base64(JwtPart),
+    Json(JwtPart, String),
+    NotAnObject(JwtPart),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PartCount(5) => write!(f, "token is encrypted (JWE) and can't be decoded"),
+            Self::PartCount(n) => write!(f, "token has {} parts, expected 3", n),
+            Self::Base64(part) => write!(f, "token {} is not valid base64url", part),
+            Self::Json(part, e) => write!(f, "token {} is not valid json: {}", part, e),
+            Self::NotAnObject(part) => write!(f, "token {} is not a json object", part),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
+
+impl From<JwtError> for JsValue {
+    fn from(err: JwtError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+/// A decoded token, e.g. from [`AuthenticationResult::access_token`](crate::AuthenticationResult::access_token).
+/// **The signature is not validated.**
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jwt {
+    header: JsonObject,
+    payload: JsonObject,
+    signature: Vec<u8>,
+    signing_input_length: usize,
+    token: String,
+}
+
+impl Jwt {
+    pub fn decode(token: &str) -> Result<Self, JwtError> {
+        let parts = token.split('.').collect::<Vec<_>>();
+        let (header, payload, signature) = match parts[..] {
+            [header, payload, signature] => (header, payload, signature),
+            _ => return Err(JwtError::PartCount(parts.len())),
+        };
+        let header = decode_object(header, JwtPart::Header)?;
+        let payload = decode_object(payload, JwtPart::Payload)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| JwtError::Base64(JwtPart::Signature))?;
+        Ok(Self {
+            header,
+            payload,
+            signature,
+            signing_input_length: token.rfind('.').unwrap(),
+            token: token.to_owned(),
+        })
+    }
+
+    pub fn header(&self) -> &JsonObject {
+        &self.header
+    }
+
+    pub fn payload(&self) -> &JsonObject {
+        &self.payload
+    }
+
+    pub fn signature(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// The `header.payload` the signature is calculated over
+    pub fn signing_input(&self) -> &str {
+        &self.token[..self.signing_input_length]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    pub fn alg(&self) -> Option<&str> {
+        self.header.get("alg").and_then(Value::as_str)
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.header.get("kid").and_then(Value::as_str)
+    }
+
+    pub fn typ(&self) -> Option<&str> {
+        self.header.get("typ").and_then(Value::as_str)
+    }
+
+    /// Get a single claim from the payload
+    pub fn claim(&self, name: &str) -> Option<&Value> {
+        self.payload.get(name)
+    }
+
+    /// The payload as [`TokenClaims`]. Object and custom claims are only kept in the browser
+    pub fn claims(&self) -> TokenClaims {
+        TokenClaims::from(&self.payload)
+    }
+}
+
+impl FromStr for Jwt {
+    type Err = JwtError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        Self::decode(token)
+    }
+}
+
+impl fmt::Display for Jwt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.token)
+    }
+}
+
+fn decode_object(part: &str, jwt_part: JwtPart) -> Result<JsonObject, JwtError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| JwtError::Base64(jwt_part))?;
+    match serde_json::from_slice(&bytes) {
+        Ok(Value::Object(obj)) => Ok(obj),
+        Ok(_) => Err(JwtError::NotAnObject(jwt_part)),
+        Err(e) => Err(JwtError::Json(jwt_part, e.to_string())),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    pub(crate) fn encode(header: &Value, payload: &Value, signature: &[u8]) -> String {
+        format!(
+            "{}.{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(payload.to_string()),
+            URL_SAFE_NO_PAD.encode(signature)
+        )
+    }
+
+    pub(crate) fn access_token() -> String {
+        encode(
+            &json!({"typ": "JWT", "alg": "RS256", "kid": "kid"}),
+            &json!({
+                "aud": "api://my-api",
+                "exp": 1536361411,
+                "scp": "User.Read Orders.Write",
+                "roles": ["Admin", "Reader"],
+            }),
+            b"signature",
+        )
+    }
+
+    #[test]
+    fn decode_jwt() {
+        let token = access_token();
+        let jwt: Jwt = token.parse().unwrap();
+        assert_eq!(jwt.alg(), Some("RS256"));
+        assert_eq!(jwt.kid(), Some("kid"));
+        assert_eq!(jwt.typ(), Some("JWT"));
+        assert_eq!(jwt.claim("aud"), Some(&json!("api://my-api")));
+        assert_eq!(jwt.claim("exp"), Some(&json!(1536361411)));
+        assert_eq!(jwt.claim("roles"), Some(&json!(["Admin", "Reader"])));
+        assert_eq!(jwt.signature(), b"signature");
+        assert!(token.starts_with(&format!("{}.", jwt.signing_input())));
+        assert_eq!(jwt.to_string(), token);
+    }
+
+    #[test]
+    fn malformed_jwt() {
+        let token = access_token();
+        let header = token.split('.').next().unwrap();
+        let not_json = URL_SAFE_NO_PAD.encode("not json");
+        let not_object = URL_SAFE_NO_PAD.encode("[]");
+
+        assert_eq!(Jwt::decode("a.b"), Err(JwtError::PartCount(2)));
+        assert_eq!(Jwt::decode("a.b.c.d.e"), Err(JwtError::PartCount(5)));
+        assert_eq!(
+            Jwt::decode(&format!("{}.*.sig", header)),
+            Err(JwtError::Base64(JwtPart::Payload))
+        );
+        assert!(matches!(
+            Jwt::decode(&format!("{}.{}.sig", header, not_json)),
+            Err(JwtError::Json(JwtPart::Payload, _))
+        ));
+        assert_eq!(
+            Jwt::decode(&format!("{}.{}.sig", not_object, not_object)),
+            Err(JwtError::NotAnObject(JwtPart::Header))
+        );
+        assert_eq!(
+            Jwt::decode(&format!("{}.{}.*", header, header)),
+            Err(JwtError::Base64(JwtPart::Signature))
+        );
+    }
+}
@@ -3,20 +3,40 @@
 // TODO: Many uses of unchecked_into... might be better to do something else:
 // Maybe consider https://docs.rs/js-sys/0.3.44/js_sys/Reflect/index.html
 // https://rustwasm.github.io/docs/wasm-bindgen/reference/working-with-duck-typed-interfaces.html
+#[cfg(feature = "serde")]
+pub mod app_state;
+pub mod cache;
+pub mod claims_request;
+#[cfg(feature = "client")]
+pub mod client;
+#[cfg(feature = "encryption")]
+pub mod encrypted_cache;
+#[cfg(feature = "idle")]
+pub mod idle;
+pub mod jwt;
 mod msal;
+pub mod pkce;
 #[cfg(feature = "popup")]
 pub mod popup;
 #[cfg(feature = "redirect")]
 pub mod redirect;
 pub mod requests;
+pub mod time;
 mod token_claims;
+pub mod token_manager;
+#[cfg(feature = "verify")]
+pub mod verify;
 
-use js_sys::{Array, Date, Function, Object};
+use cache::CacheSnapshot;
+use js_sys::{Array, Function, Object};
+use jwt::JwtError;
 use msal::JsArrayString;
 use requests::*;
 use std::borrow::{Borrow, Cow};
 use std::convert::{TryFrom, TryInto};
-use token_claims::TokenClaims;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+pub use token_claims::{Actor, Address, ClaimValue, Confirmation, TokenClaim, TokenClaims};
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 
 pub struct BrowserAuthOptions<'a> {
@@ -314,6 +334,11 @@ impl LoggerOptions {
         self
     }
 
+    /// Defaults to false as in msal
+    pub fn pii_logging_enabled(&self) -> bool {
+        self.pii_logging_enabled.unwrap_or_default()
+    }
+
     pub fn set_log_level(mut self, log_level: LogLevel) -> Self {
         self.log_level = Some(log_level);
         self
@@ -489,8 +514,8 @@ impl<'a> From<msal::Configuration> for Configuration<'a> {
     }
 }
 
-// TODO: Date is a Js type, should I change?
 //file://./../node_modules/@azure/msal-common/dist/src/response/AuthenticationResult.d.ts
+/// `Debug` and `Serialize` mask the tokens
 #[derive(Clone)]
 pub struct AuthenticationResult {
     unique_id: String,
@@ -499,12 +524,23 @@ pub struct AuthenticationResult {
     account: AccountInfo,
     id_token: String,
     id_token_claims: TokenClaims,
+    // Kept to deserialize into user types
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    id_token_claims_object: Object,
     access_token: String,
     from_cache: bool,
-    expires_on: Date,
-    ext_expires_on: Option<Date>,
+    expires_on: SystemTime,
+    ext_expires_on: Option<SystemTime>,
     state: Option<String>,
     family_id: Option<String>,
+    token_type: String,
+    correlation_id: String,
+    cloud_graph_host_name: Option<String>,
+    msgraph_host: Option<String>,
+    code: Option<String>,
+    from_native_broker: bool,
+    request_id: Option<String>,
+    refresh_on: Option<SystemTime>,
 }
 
 impl AuthenticationResult {
@@ -532,22 +568,55 @@ impl AuthenticationResult {
         &self.id_token_claims
     }
 
+    /// Deserialize the claims into your own type, e.g. one shared with a backend,
+    /// to pick up custom claims such as `extension_<appid>_<name>`
+    #[cfg(feature = "serde")]
+    pub fn id_token_claims_as<T>(&self) -> Result<T, JsValue>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        Ok(serde_wasm_bindgen::from_value(
+            self.id_token_claims_object.clone().into(),
+        )?)
+    }
+
     pub fn access_token(&self) -> &str {
         &self.access_token
     }
 
+    /// Decodes the access token claims **without validating the signature**.
+    /// Will error for tokens that aren't JWTs, such as those issued for a Microsoft account
+    pub fn access_token_claims(&self) -> Result<TokenClaims, JwtError> {
+        TokenClaims::from_jwt(&self.access_token)
+    }
+
     pub fn from_cache(&self) -> &bool {
         &self.from_cache
     }
 
-    pub fn expires_on(&self) -> &Date {
+    pub fn expires_on(&self) -> &SystemTime {
         &self.expires_on
     }
 
-    pub fn ext_expires_on(&self) -> Option<&Date> {
+    pub fn ext_expires_on(&self) -> Option<&SystemTime> {
         self.ext_expires_on.as_ref()
     }
 
+    /// The access token has expired, allowing for the local clock being up to `skew` ahead
+    pub fn is_expired(&self, skew: Duration) -> bool {
+        time::is_expired(self.expires_on, skew)
+    }
+
+    /// Zero once expired
+    pub fn expires_in(&self) -> Duration {
+        time::expires_in(self.expires_on)
+    }
+
+    /// The access token expires within `offset`, e.g. the `token_renewal_offset_seconds`
+    pub fn should_refresh(&self, offset: Duration) -> bool {
+        time::should_refresh(self.expires_on, offset)
+    }
+
     pub fn state(&self) -> Option<&str> {
         self.state.as_deref()
     }
@@ -555,33 +624,302 @@ impl AuthenticationResult {
     pub fn family_id(&self) -> Option<&str> {
         self.family_id.as_deref()
     }
+
+    /// The authentication scheme the token was issued for, `Bearer` or `pop`
+    pub fn token_type(&self) -> &str {
+        &self.token_type
+    }
+
+    /// Quote this when raising a support ticket
+    pub fn correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    pub fn cloud_graph_host_name(&self) -> Option<&str> {
+        self.cloud_graph_host_name.as_deref()
+    }
+
+    /// The graph endpoint for the account's cloud, e.g. `graph.microsoft.us` in a sovereign cloud
+    pub fn msgraph_host(&self) -> Option<&str> {
+        self.msgraph_host.as_deref()
+    }
+
+    /// The `spa_code` for the hybrid flow, if requested
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn from_native_broker(&self) -> bool {
+        self.from_native_broker
+    }
+
+    /// The `x-ms-request-id` of the token request, `None` when served from the cache
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// When the server has asked for the token to be refreshed, before it expires
+    pub fn refresh_on(&self) -> Option<&SystemTime> {
+        self.refresh_on.as_ref()
+    }
+
+    /// See [`TokenClaims::redacted`], also redacts the account
+    pub fn redacted(&self, pii_logging_enabled: bool) -> Self {
+        let id_token_claims = self.id_token_claims.redacted(pii_logging_enabled);
+        Self {
+            id_token_claims_object: id_token_claims.to_object(),
+            id_token_claims,
+            account: self.account.redacted(pii_logging_enabled),
+            ..self.clone()
+        }
+    }
 }
 
-impl From<msal::AuthenticationResult> for AuthenticationResult {
-    fn from(auth_result: msal::AuthenticationResult) -> Self {
+const REDACTED: &str = "<redacted>";
+
+fn mask_token(token: &str) -> String {
+    format!("<{} byte token>", token.len())
+}
+
+impl fmt::Debug for AuthenticationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthenticationResult")
+            .field("unique_id", &self.unique_id)
+            .field("tenant_id", &self.tenant_id)
+            .field("scopes", &self.scopes)
+            .field("account", &self.account)
+            .field("id_token", &mask_token(&self.id_token))
+            .field("id_token_claims", &self.id_token_claims)
+            .field("access_token", &mask_token(&self.access_token))
+            .field("from_cache", &self.from_cache)
+            .field("expires_on", &self.expires_on)
+            .field("ext_expires_on", &self.ext_expires_on)
+            .field("state", &self.state)
+            .field("family_id", &self.family_id)
+            .field("token_type", &self.token_type)
+            .field("correlation_id", &self.correlation_id)
+            .field("cloud_graph_host_name", &self.cloud_graph_host_name)
+            .field("msgraph_host", &self.msgraph_host)
+            .field("code", &self.code.as_deref().map(mask_token))
+            .field("from_native_broker", &self.from_native_broker)
+            .field("request_id", &self.request_id)
+            .field("refresh_on", &self.refresh_on)
+            .finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AuthenticationResult {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("AuthenticationResult", 20)?;
+        s.serialize_field("unique_id", &self.unique_id)?;
+        s.serialize_field("tenant_id", &self.tenant_id)?;
+        s.serialize_field("scopes", &self.scopes)?;
+        s.serialize_field("account", &self.account)?;
+        s.serialize_field("id_token", &mask_token(&self.id_token))?;
+        s.serialize_field("id_token_claims", &self.id_token_claims)?;
+        s.serialize_field("access_token", &mask_token(&self.access_token))?;
+        s.serialize_field("from_cache", &self.from_cache)?;
+        s.serialize_field("expires_on", &time::to_seconds(self.expires_on))?;
+        s.serialize_field("ext_expires_on", &self.ext_expires_on.map(time::to_seconds))?;
+        s.serialize_field("state", &self.state)?;
+        s.serialize_field("family_id", &self.family_id)?;
+        s.serialize_field("token_type", &self.token_type)?;
+        s.serialize_field("correlation_id", &self.correlation_id)?;
+        s.serialize_field("cloud_graph_host_name", &self.cloud_graph_host_name)?;
+        s.serialize_field("msgraph_host", &self.msgraph_host)?;
+        s.serialize_field("code", &self.code.as_deref().map(mask_token))?;
+        s.serialize_field("from_native_broker", &self.from_native_broker)?;
+        s.serialize_field("request_id", &self.request_id)?;
+        s.serialize_field("refresh_on", &self.refresh_on.map(time::to_seconds))?;
+        s.end()
+    }
+}
+
+impl From<AuthenticationResultRef> for AuthenticationResult {
+    fn from(auth_result: AuthenticationResultRef) -> Self {
+        let id_token_claims_object = auth_result.0.id_token_claims();
         Self {
             unique_id: auth_result.unique_id(),
             tenant_id: auth_result.tenant_id(),
-            scopes: JsArrayString::from(auth_result.scopes()).0,
-            account: auth_result.account().into(),
+            scopes: auth_result.scopes(),
+            account: auth_result.account(),
             id_token: auth_result.id_token(),
-            id_token_claims: auth_result.id_token_claims().into(),
+            id_token_claims: id_token_claims_object.clone().into(),
+            id_token_claims_object,
             access_token: auth_result.access_token(),
             from_cache: auth_result.from_cache(),
             expires_on: auth_result.expires_on(),
             ext_expires_on: auth_result.ext_expires_on(),
             state: auth_result.state(),
             family_id: auth_result.family_id(),
+            token_type: auth_result.token_type(),
+            correlation_id: auth_result.correlation_id(),
+            cloud_graph_host_name: auth_result.cloud_graph_host_name(),
+            msgraph_host: auth_result.msgraph_host(),
+            code: auth_result.code(),
+            from_native_broker: auth_result.from_native_broker(),
+            request_id: auth_result.request_id(),
+            refresh_on: auth_result.refresh_on(),
         }
     }
 }
 
+impl From<msal::AuthenticationResult> for AuthenticationResult {
+    fn from(auth_result: msal::AuthenticationResult) -> Self {
+        AuthenticationResultRef(auth_result).into()
+    }
+}
+
 impl From<JsValue> for AuthenticationResult {
     fn from(value: JsValue) -> Self {
         value.unchecked_into::<msal::AuthenticationResult>().into()
     }
 }
 
+/// A lazy [`AuthenticationResult`] that keeps the js object and only converts a field when
+/// it is accessed. Cheaper when only the `access_token` is needed, e.g. a silent cache hit
+/// before each api call. The getters copy out of js on every call.
+pub struct AuthenticationResultRef(msal::AuthenticationResult);
+
+impl Clone for AuthenticationResultRef {
+    fn clone(&self) -> Self {
+        Self(self.0.clone().unchecked_into())
+    }
+}
+
+impl AuthenticationResultRef {
+    pub fn unique_id(&self) -> String {
+        self.0.unique_id()
+    }
+
+    pub fn tenant_id(&self) -> String {
+        self.0.tenant_id()
+    }
+
+    pub fn scopes(&self) -> Vec<String> {
+        JsArrayString::from(self.0.scopes()).0
+    }
+
+    pub fn account(&self) -> AccountInfo {
+        self.0.account().into()
+    }
+
+    pub fn id_token(&self) -> String {
+        self.0.id_token()
+    }
+
+    pub fn id_token_claims(&self) -> TokenClaims {
+        self.0.id_token_claims().into()
+    }
+
+    pub fn access_token(&self) -> String {
+        self.0.access_token()
+    }
+
+    /// See [`AuthenticationResult::access_token_claims`]
+    pub fn access_token_claims(&self) -> Result<TokenClaims, JwtError> {
+        TokenClaims::from_jwt(&self.access_token())
+    }
+
+    pub fn from_cache(&self) -> bool {
+        self.0.from_cache()
+    }
+
+    pub fn expires_on(&self) -> SystemTime {
+        time::from_js_date(&self.0.expires_on())
+    }
+
+    pub fn ext_expires_on(&self) -> Option<SystemTime> {
+        self.0.ext_expires_on().map(|v| time::from_js_date(&v))
+    }
+
+    /// See [`AuthenticationResult::is_expired`]
+    pub fn is_expired(&self, skew: Duration) -> bool {
+        time::is_expired(self.expires_on(), skew)
+    }
+
+    pub fn expires_in(&self) -> Duration {
+        time::expires_in(self.expires_on())
+    }
+
+    pub fn should_refresh(&self, offset: Duration) -> bool {
+        time::should_refresh(self.expires_on(), offset)
+    }
+
+    pub fn state(&self) -> Option<String> {
+        self.0.state()
+    }
+
+    pub fn family_id(&self) -> Option<String> {
+        self.0.family_id()
+    }
+
+    pub fn token_type(&self) -> String {
+        self.0.token_type()
+    }
+
+    pub fn correlation_id(&self) -> String {
+        self.0.correlation_id()
+    }
+
+    // msal uses an empty string for missing values
+    pub fn cloud_graph_host_name(&self) -> Option<String> {
+        self.0.cloud_graph_host_name().filter(|v| !v.is_empty())
+    }
+
+    pub fn msgraph_host(&self) -> Option<String> {
+        self.0.msgraph_host().filter(|v| !v.is_empty())
+    }
+
+    pub fn code(&self) -> Option<String> {
+        self.0.code().filter(|v| !v.is_empty())
+    }
+
+    pub fn from_native_broker(&self) -> bool {
+        self.0.from_native_broker().unwrap_or_default()
+    }
+
+    pub fn request_id(&self) -> Option<String> {
+        self.0.request_id().filter(|v| !v.is_empty())
+    }
+
+    pub fn refresh_on(&self) -> Option<SystemTime> {
+        self.0.refresh_on().map(|v| time::from_js_date(&v))
+    }
+
+    /// Convert every field
+    pub fn into_owned(self) -> AuthenticationResult {
+        self.into()
+    }
+}
+
+impl fmt::Debug for AuthenticationResultRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthenticationResultRef")
+            .field("unique_id", &self.unique_id())
+            .field("access_token", &mask_token(&self.access_token()))
+            .field("expires_on", &self.expires_on())
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<msal::AuthenticationResult> for AuthenticationResultRef {
+    fn from(auth_result: msal::AuthenticationResult) -> Self {
+        Self(auth_result)
+    }
+}
+
+impl From<JsValue> for AuthenticationResultRef {
+    fn from(value: JsValue) -> Self {
+        Self(value.unchecked_into())
+    }
+}
+
 pub trait PublicClientApplication: msal::Msal {
     fn client_id(&self) -> String {
         self.auth().config().auth().client_id()
@@ -614,6 +952,34 @@ pub trait PublicClientApplication: msal::Msal {
     fn logout(&self, request: Option<EndSessionRequest>) {
         self.auth().logout(request.unwrap_or_default().into())
     }
+
+    /// Lists what msal has cached, without the tokens
+    fn cache_snapshot(&self) -> Result<CacheSnapshot, JsValue> {
+        cache::snapshot(self.auth())
+    }
+}
+
+/// Clears the cache for the account, or all accounts, without navigating to the end session
+/// endpoint so the user stays signed in to their identity provider
+async fn logout_local(
+    client_app: &msal::PublicClientApplication,
+    request: EndSessionRequest<'_>,
+) -> Result<(), JsValue> {
+    let request = msal::EndSessionRequest::from(request);
+    request.set_on_redirect_navigate(&Function::new_no_args("return false"));
+    client_app.logout_redirect(request).await.map(|_| ())
+}
+
+// Called by both popup and redirect, and the token manager
+async fn clear_cache(
+    client_app: &msal::PublicClientApplication,
+    account: Option<&AccountInfo>,
+) -> Result<(), JsValue> {
+    let request = match account {
+        Some(v) => EndSessionRequest::new().set_account(v),
+        None => EndSessionRequest::new(),
+    };
+    logout_local(client_app, request).await
 }
 
 // Can't put these on the trait since `async` is not allowed in traits
@@ -626,6 +992,7 @@ async fn sso_silent<'a>(
     client_app: &msal::PublicClientApplication,
     request: &'a AuthorizationUrlRequest<'a>,
 ) -> Result<AuthenticationResult, JsValue> {
+    request.check_response_mode()?;
     client_app.sso_silent(request.into()).await.map(Into::into)
 }
 
@@ -642,12 +1009,45 @@ async fn acquire_token_silent<'a>(
         .map(Into::into)
 }
 
-#[derive(Clone)]
+// Called by both popup and redirect, for the scope only logins
+fn scopes_to_vec<T>(scopes: &[T]) -> Vec<String>
+where
+    T: Into<String> + Clone,
+{
+    scopes.iter().cloned().map(Into::into).collect()
+}
+
+// Called by both popup and redirect
+async fn acquire_token_silent_ref<'a>(
+    client_app: &msal::PublicClientApplication,
+    request: &'a SilentRequest<'a>,
+) -> Result<AuthenticationResultRef, JsValue> {
+    client_app
+        .acquire_token_silent(request.into())
+        .await
+        .map(Into::into)
+}
+
+// Called by both popup and redirect
+// https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-browser/docs/spa-auth-code.md
+// Redeems the `spa_code` returned to the server, msal raises the `ACQUIRE_TOKEN_BY_CODE_*` events
+async fn acquire_token_by_code<'a>(
+    client_app: &msal::PublicClientApplication,
+    request: &'a AuthorizationCodeRequest<'a>,
+) -> Result<AuthenticationResult, JsValue> {
+    client_app
+        .acquire_token_by_code(request.into())
+        .await
+        .map(Into::into)
+}
+
+#[derive(Clone, Debug)]
 pub struct AccountInfo {
     home_account_id: String,
     environment: String,
     tenant_id: String,
     username: String,
+    id_token_claims: Option<Object>,
 }
 
 impl AccountInfo {
@@ -667,6 +1067,36 @@ impl AccountInfo {
         &self.username
     }
 
+    pub fn id_token_claims(&self) -> Option<TokenClaims> {
+        self.id_token_claims.clone().map(TokenClaims::from)
+    }
+
+    /// See [`TokenClaims::redacted`], the username is replaced as it is the `preferred_username`
+    pub fn redacted(&self, pii_logging_enabled: bool) -> Self {
+        if pii_logging_enabled {
+            return self.clone();
+        }
+        Self {
+            username: REDACTED.to_owned(),
+            id_token_claims: self
+                .id_token_claims()
+                .map(|v| v.redacted(false).to_object()),
+            ..self.clone()
+        }
+    }
+
+    /// See [`AuthenticationResult::id_token_claims_as`], `None` if the account has no claims
+    #[cfg(feature = "serde")]
+    pub fn id_token_claims_as<T>(&self) -> Result<Option<T>, JsValue>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match &self.id_token_claims {
+            Some(v) => Ok(Some(serde_wasm_bindgen::from_value(v.clone().into())?)),
+            None => Ok(None),
+        }
+    }
+
     fn from_array(array: Array) -> Vec<Self> {
         array
             .iter()
@@ -675,6 +1105,34 @@ impl AccountInfo {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for AccountInfo {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("AccountInfo", 5)?;
+        s.serialize_field("home_account_id", &self.home_account_id)?;
+        s.serialize_field("environment", &self.environment)?;
+        s.serialize_field("tenant_id", &self.tenant_id)?;
+        s.serialize_field("username", &self.username)?;
+        s.serialize_field("id_token_claims", &self.id_token_claims())?;
+        s.end()
+    }
+}
+
+/// The claims are left out, they are a js object so would only be equal if the same
+/// instance, and come from the id token of the same account anyway
+impl PartialEq for AccountInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.home_account_id == other.home_account_id
+            && self.environment == other.environment
+            && self.tenant_id == other.tenant_id
+            && self.username == other.username
+    }
+}
+
 impl From<msal::AccountInfo> for AccountInfo {
     fn from(account_info: msal::AccountInfo) -> Self {
         Self {
@@ -682,28 +1140,59 @@ impl From<msal::AccountInfo> for AccountInfo {
             environment: account_info.environment(),
             tenant_id: account_info.tenant_id(),
             username: account_info.username(),
+            id_token_claims: account_info.id_token_claims(),
         }
     }
 }
 
 impl<'a> From<&'a AccountInfo> for msal::AccountInfo {
     fn from(account_info: &'a AccountInfo) -> Self {
-        msal::AccountInfo::new(
+        let js = msal::AccountInfo::new(
             &account_info.home_account_id,
             &account_info.environment,
             &account_info.tenant_id,
             &account_info.username,
-        )
+        );
+        if let Some(v) = &account_info.id_token_claims {
+            js.set_id_token_claims(v)
+        }
+        js
+    }
+}
+
+impl<'a> From<&'a AccountInfo> for Cow<'a, AccountInfo> {
+    fn from(account_info: &'a AccountInfo) -> Self {
+        Cow::Borrowed(account_info)
+    }
+}
+
+impl<'a> From<AccountInfo> for Cow<'a, AccountInfo> {
+    fn from(account_info: AccountInfo) -> Self {
+        Cow::Owned(account_info)
     }
 }
 
 //TODO: Should I just remove and pub in root?
 pub mod prelude {
+    #[cfg(feature = "serde")]
+    pub use crate::app_state::AppState;
+    pub use crate::cache::CacheSnapshot;
+    pub use crate::claims_request::*;
+    #[cfg(feature = "client")]
+    pub use crate::client::{AuthenticatedClient, ClientError, ProtectedResourceMap};
+    #[cfg(feature = "encryption")]
+    pub use crate::encrypted_cache::EncryptedCache;
+    #[cfg(feature = "idle")]
+    pub use crate::idle::{ActivitySync, IdleEvent, IdleHandle, IdleLogout, IdleMonitor};
+    pub use crate::jwt::Jwt;
+    pub use crate::pkce::PkceCodes;
     pub use crate::popup::PopupApp;
     pub use crate::requests::*;
+    pub use crate::token_manager::{TokenKey, TokenManager};
     pub use crate::{
-        AccountInfo, AuthenticationResult, BrowserAuthOptions, CacheLocation, CacheOptions,
-        Configuration, LogLevel, LoggerOptions, PublicClientApplication,
+        AccountInfo, AuthenticationResult, AuthenticationResultRef, BrowserAuthOptions,
+        CacheLocation, CacheOptions, Configuration, LogLevel, LoggerOptions,
+        PublicClientApplication, TokenClaim, TokenClaims,
     };
 }
 
@@ -760,6 +1249,7 @@ mod tests {
             environment: ENVIRONMENT.to_string(),
             tenant_id: TENANT_ID.to_string(),
             username: USERNAME.to_string(),
+            id_token_claims: None,
         }
     }
 
@@ -814,10 +1304,195 @@ mod tests {
 
     #[wasm_bindgen_test]
     fn parse_js_authentication_result() {
-        let _: AuthenticationResult = authResponse
+        let auth_result: AuthenticationResult = authResponse
+            .clone()
+            .unchecked_into::<msal::AuthenticationResult>()
+            .into();
+        // Thu Aug 06 2020 10:35:12 GMT+1000
+        let expires_on = time::from_seconds(1596674112.0);
+        assert_eq!(auth_result.expires_on(), &expires_on);
+        assert_eq!(auth_result.ext_expires_on(), Some(&expires_on));
+        assert!(auth_result.is_expired(Duration::ZERO));
+        assert!(auth_result.should_refresh(Duration::from_secs(300)));
+        assert_eq!(auth_result.expires_in(), Duration::ZERO);
+
+        assert_eq!(auth_result.unique_id(), "uniqueId");
+        assert_eq!(auth_result.tenant_id(), "tenantId");
+        assert_eq!(auth_result.scopes(), &["openid", "profile", "email"]);
+        assert_eq!(auth_result.account().home_account_id(), "homeAccountId");
+        assert_eq!(auth_result.account().username(), "username");
+        assert_eq!(auth_result.id_token(), "idToken");
+        assert_eq!(
+            auth_result.id_token_claims().oid().map(String::as_str),
+            Some("00000000-0000-0000-66f3-3332eca7ea81")
+        );
+        assert_eq!(auth_result.access_token(), "accessToken");
+        assert!(*auth_result.from_cache());
+        assert_eq!(auth_result.state(), Some("state"));
+        assert_eq!(auth_result.family_id(), Some("familyId"));
+        assert_eq!(auth_result.token_type(), "Bearer");
+        assert_eq!(
+            auth_result.correlation_id(),
+            "8f4b1b4e-3e0f-4f8a-9a55-2a6f8e3c1d7b"
+        );
+        assert_eq!(
+            auth_result.cloud_graph_host_name(),
+            Some("graph.windows.net")
+        );
+        assert_eq!(auth_result.msgraph_host(), Some("graph.microsoft.com"));
+        assert_eq!(auth_result.code(), Some("spaCode"));
+        assert!(!auth_result.from_native_broker());
+        assert_eq!(
+            auth_result.request_id(),
+            Some("c1f2e3d4-0000-4000-8000-000000000000")
+        );
+        // Thu Aug 06 2020 10:05:12 GMT+1000
+        assert_eq!(
+            auth_result.refresh_on(),
+            Some(&time::from_seconds(1596672312.0))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn lazy_authentication_result() {
+        let auth_result: AuthenticationResultRef = JsValue::from(authResponse.clone()).into();
+        let owned: AuthenticationResult = JsValue::from(authResponse.clone()).into();
+        assert_eq!(auth_result.access_token(), owned.access_token());
+        assert_eq!(&auth_result.expires_on(), owned.expires_on());
+        assert_eq!(&auth_result.id_token_claims(), owned.id_token_claims());
+        assert_eq!(auth_result.msgraph_host().as_deref(), owned.msgraph_host());
+        assert_eq!(auth_result.refresh_on().as_ref(), owned.refresh_on());
+
+        let into_owned = auth_result.clone().into_owned();
+        assert_eq!(into_owned.scopes(), owned.scopes());
+        assert_eq!(into_owned.account(), owned.account());
+        assert_eq!(into_owned.correlation_id(), owned.correlation_id());
+        assert_eq!(into_owned.code(), owned.code());
+    }
+
+    #[wasm_bindgen_test]
+    fn account_equality() {
+        let with_claims = |claims: &str| AccountInfo {
+            id_token_claims: Some(js_sys::JSON::parse(claims).unwrap().unchecked_into()),
+            ..account()
+        };
+        assert_eq!(
+            with_claims(r#"{"name":"user"}"#),
+            with_claims(r#"{"name":"user"}"#)
+        );
+        assert_eq!(with_claims("{}"), account());
+        let other = AccountInfo {
+            username: "other".into(),
+            ..account()
+        };
+        assert_ne!(other, account());
+        assert_eq!(
+            SilentRequest::new(&[SCOPE][..], with_claims("{}")),
+            SilentRequest::new(&[SCOPE][..], with_claims("{}"))
+        );
+    }
+
+    // Not a rigorous benchmark, but shows the saving from only reading the `access_token`
+    #[wasm_bindgen_test]
+    fn bench_lazy_authentication_result() {
+        const RUNS: usize = 1000;
+        let time = |f: &dyn Fn() -> usize| {
+            let start = js_sys::Date::now();
+            let total = (0..RUNS).map(|_| f()).sum::<usize>();
+            (js_sys::Date::now() - start, total)
+        };
+        let (owned_ms, owned_total) = time(&|| {
+            AuthenticationResult::from(JsValue::from(authResponse.clone()))
+                .access_token()
+                .len()
+        });
+        let (lazy_ms, lazy_total) = time(&|| {
+            AuthenticationResultRef::from(JsValue::from(authResponse.clone()))
+                .access_token()
+                .len()
+        });
+        console::log_1(
+            &format!(
+                "{} access tokens: owned {}ms, lazy {}ms",
+                RUNS, owned_ms, lazy_ms
+            )
+            .into(),
+        );
+        assert_eq!(owned_total, lazy_total);
+    }
+
+    #[wasm_bindgen_test]
+    fn redact_authentication_result() {
+        let auth_result: AuthenticationResult = authResponse
+            .clone()
+            .unchecked_into::<msal::AuthenticationResult>()
+            .into();
+        let debug = format!("{:?}", auth_result);
+        assert!(!debug.contains("\"accessToken\""));
+        assert!(debug.contains("<11 byte token>"));
+
+        let redacted = auth_result.redacted(false);
+        assert!(redacted.id_token_claims().name().is_none());
+        assert!(redacted.id_token_claims().preferred_username().is_none());
+        assert_eq!(redacted.account().username(), "<redacted>");
+        assert_eq!(
+            redacted.account().id_token_claims().as_ref(),
+            Some(redacted.id_token_claims())
+        );
+        assert_eq!(
+            redacted.id_token_claims().oid(),
+            auth_result.id_token_claims().oid()
+        );
+        assert_eq!(
+            auth_result.redacted(true).id_token_claims(),
+            auth_result.id_token_claims()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[wasm_bindgen_test]
+    fn serialize_authentication_result() {
+        let auth_result: AuthenticationResult = authResponse
+            .clone()
+            .unchecked_into::<msal::AuthenticationResult>()
+            .into();
+        let json = serde_json::to_value(&auth_result).unwrap();
+        assert_eq!(json["access_token"], "<11 byte token>");
+        assert_eq!(json["expires_on"], 1596674112.0);
+        assert_eq!(json["account"]["username"], "username");
+        assert_eq!(
+            json["id_token_claims"]["oid"],
+            "00000000-0000-0000-66f3-3332eca7ea81"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[wasm_bindgen_test]
+    fn deserialize_id_token_claims() {
+        #[derive(serde::Deserialize)]
+        struct MyClaims {
+            oid: String,
+            name: String,
+            // Missing claims need to be optional
+            extension_department: Option<String>,
+        }
+
+        let auth_result: AuthenticationResult = authResponse
             .clone()
             .unchecked_into::<msal::AuthenticationResult>()
             .into();
+        let claims: MyClaims = auth_result.id_token_claims_as().unwrap();
+        assert_eq!(Some(&claims.oid), auth_result.id_token_claims().oid());
+        assert_eq!(Some(&claims.name), auth_result.id_token_claims().name());
+        assert!(claims.extension_department.is_none());
+
+        let account_claims: MyClaims = auth_result.account().id_token_claims_as().unwrap().unwrap();
+        assert_eq!(account_claims.oid, claims.oid);
+        assert!(account()
+            .id_token_claims_as::<MyClaims>()
+            .unwrap()
+            .is_none());
+        assert!(auth_result.id_token_claims_as::<u32>().is_err());
     }
 
     #[wasm_bindgen_test]
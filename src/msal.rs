@@ -11,7 +11,7 @@
 //! LogLevel, Logger, PublicClientApplication };
 //! ```
 
-use js_sys::{Array, Date, Function, JsString, Map, Object};
+use js_sys::{Array, Function, JsString, Object, Promise, Reflect};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
@@ -156,30 +156,35 @@ extern "C" {
     #[wasm_bindgen(constructor)]
     pub fn new(scopes: &Array) -> AuthorizationUrlRequest;
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn authority(this: &AuthorizationUrlRequest) -> String;
+    pub fn scopes(this: &AuthorizationUrlRequest) -> Option<Array>;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn authority(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_authority(this: &AuthorizationUrlRequest, authority: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = correlationId)]
-    pub fn correlation_id(this: &AuthorizationUrlRequest) -> String;
+    pub fn correlation_id(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = correlationId)]
     pub fn set_correlation_id(this: &AuthorizationUrlRequest, correlation_id: &str);
 
-    #[cfg(test)]
+    #[wasm_bindgen(method, getter = authenticationScheme)]
+    pub fn authentication_scheme(this: &AuthorizationUrlRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = authenticationScheme)]
+    pub fn set_authentication_scheme(this: &AuthorizationUrlRequest, authentication_scheme: &str);
+
     #[wasm_bindgen(method, getter = redirectUri)]
-    pub fn redirect_uri(this: &AuthorizationUrlRequest) -> String;
+    pub fn redirect_uri(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = redirectUri)]
     pub fn set_redirect_uri(this: &AuthorizationUrlRequest, redirect_uri: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = extraScopesToConsent)]
-    pub fn extra_scopes_to_consent(this: &AuthorizationUrlRequest) -> String;
+    pub fn extra_scopes_to_consent(this: &AuthorizationUrlRequest) -> Option<Array>;
 
     #[wasm_bindgen(method, setter = extraScopesToConsent)]
     pub fn set_extra_scopes_to_consent(
@@ -187,73 +192,66 @@ extern "C" {
         extra_scopes_to_consent: Array,
     );
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = responseMode)]
-    pub fn response_mode(this: &AuthorizationUrlRequest) -> String;
+    pub fn response_mode(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = responseMode)]
     pub fn set_response_mode(this: &AuthorizationUrlRequest, response_mode: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = codeChallenge)]
-    pub fn code_challenge(this: &AuthorizationUrlRequest) -> String;
+    pub fn code_challenge(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = codeChallenge)]
     pub fn set_code_challenge(this: &AuthorizationUrlRequest, code_challenge: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = codeChallengeMethod)]
-    pub fn code_challenge_method(this: &AuthorizationUrlRequest) -> String;
+    pub fn code_challenge_method(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = codeChallengeMethod)]
     pub fn set_code_challenge_method(this: &AuthorizationUrlRequest, code_challenge_method: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn state(this: &AuthorizationUrlRequest) -> String;
+    pub fn state(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_state(this: &AuthorizationUrlRequest, state: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn prompt(this: &AuthorizationUrlRequest) -> String;
+    pub fn prompt(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_prompt(this: &AuthorizationUrlRequest, prompt: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = loginHint)]
-    pub fn login_hint(this: &AuthorizationUrlRequest) -> String;
+    pub fn login_hint(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = loginHint)]
     pub fn set_login_hint(this: &AuthorizationUrlRequest, login_hint: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = domainHint)]
-    pub fn domain_hint(this: &AuthorizationUrlRequest) -> String;
+    pub fn domain_hint(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = domainHint)]
     pub fn set_domain_hint(this: &AuthorizationUrlRequest, domain_hint: &str);
 
-    // Hashmap<&str, &str>
-    #[cfg(test)]
+    // Record<string, string>
     #[wasm_bindgen(method, getter = extraQueryParameters)]
-    pub fn extra_query_parameters(this: &AuthorizationUrlRequest) -> Object;
+    pub fn extra_query_parameters(this: &AuthorizationUrlRequest) -> Option<Object>;
 
     #[wasm_bindgen(method, setter = extraQueryParameters)]
-    pub fn set_extra_query_parameters(this: &AuthorizationUrlRequest, claims: Map);
+    pub fn set_extra_query_parameters(
+        this: &AuthorizationUrlRequest,
+        extra_query_parameters: Object,
+    );
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn claims(this: &AuthorizationUrlRequest) -> String;
+    pub fn claims(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_claims(this: &AuthorizationUrlRequest, claims: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn nonce(this: &AuthorizationUrlRequest) -> String;
+    pub fn nonce(this: &AuthorizationUrlRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_nonce(this: &AuthorizationUrlRequest, nonce: &str);
@@ -281,146 +279,146 @@ extern "C" {
     #[wasm_bindgen(method, getter)]
     pub fn username(this: &AccountInfo) -> String;
 
+    #[wasm_bindgen(method, getter = idTokenClaims)]
+    pub fn id_token_claims(this: &AccountInfo) -> Option<Object>;
+
+    #[wasm_bindgen(method, setter = idTokenClaims)]
+    pub fn set_id_token_claims(this: &AccountInfo, id_token_claims: &Object);
+
     // file://./..//node_modules/@azure/msal-common/dist/request/EndSessionRequest.d.ts
     pub type EndSessionRequest;
 
     #[wasm_bindgen(constructor)]
     pub fn new() -> EndSessionRequest;
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     pub fn account(this: &EndSessionRequest) -> Option<AccountInfo>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_account(this: &EndSessionRequest, account: AccountInfo);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = postLogoutRedirectUri)]
     pub fn post_logout_redirect_uri(this: &EndSessionRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = postLogoutRedirectUri)]
     pub fn set_post_logout_redirect_uri(this: &EndSessionRequest, post_logout_redirect_uri: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     pub fn authority(this: &EndSessionRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_authority(this: &EndSessionRequest, authority: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = correlationId)]
     pub fn correlation_id(this: &EndSessionRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = correlationId)]
     pub fn set_correlation_id(this: &EndSessionRequest, correlation_id: &str);
 
+    // From RedirectEndSessionRequest, returning false clears the cache without navigating
+    #[wasm_bindgen(method, setter = onRedirectNavigate)]
+    pub fn set_on_redirect_navigate(this: &EndSessionRequest, on_redirect_navigate: &Function);
+
     // file://./..//node_modules/@azure/msal-browser/dist/request/RedirectRequest.d.ts
     pub type RedirectRequest;
 
     #[wasm_bindgen(constructor)]
     pub fn new(scopes: &Array) -> RedirectRequest;
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn authority(this: &RedirectRequest) -> String;
+    pub fn scopes(this: &RedirectRequest) -> Option<Array>;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn authority(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_authority(this: &RedirectRequest, authority: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = correlationId)]
-    pub fn correlation_id(this: &RedirectRequest) -> String;
+    pub fn correlation_id(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = correlationId)]
     pub fn set_correlation_id(this: &RedirectRequest, correlation_id: &str);
 
-    #[cfg(test)]
+    #[wasm_bindgen(method, getter = authenticationScheme)]
+    pub fn authentication_scheme(this: &RedirectRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = authenticationScheme)]
+    pub fn set_authentication_scheme(this: &RedirectRequest, authentication_scheme: &str);
+
     #[wasm_bindgen(method, getter = redirectUri)]
-    pub fn redirect_uri(this: &RedirectRequest) -> String;
+    pub fn redirect_uri(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = redirectUri)]
     pub fn set_redirect_uri(this: &RedirectRequest, redirect_uri: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = extraScopesToConsent)]
-    pub fn extra_scopes_to_consent(this: &RedirectRequest) -> String;
+    pub fn extra_scopes_to_consent(this: &RedirectRequest) -> Option<Array>;
 
     #[wasm_bindgen(method, setter = extraScopesToConsent)]
     pub fn set_extra_scopes_to_consent(this: &RedirectRequest, extra_scopes_to_consent: Array);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = responseMode)]
-    pub fn response_mode(this: &RedirectRequest) -> String;
+    pub fn response_mode(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = responseMode)]
     pub fn set_response_mode(this: &RedirectRequest, response_mode: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = codeChallenge)]
-    pub fn code_challenge(this: &RedirectRequest) -> String;
+    pub fn code_challenge(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = codeChallenge)]
     pub fn set_code_challenge(this: &RedirectRequest, code_challenge: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = codeChallengeMethod)]
-    pub fn code_challenge_method(this: &RedirectRequest) -> String;
+    pub fn code_challenge_method(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = codeChallengeMethod)]
     pub fn set_code_challenge_method(this: &RedirectRequest, code_challenge_method: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn state(this: &RedirectRequest) -> String;
+    pub fn state(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_state(this: &RedirectRequest, state: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn prompt(this: &RedirectRequest) -> String;
+    pub fn prompt(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_prompt(this: &RedirectRequest, prompt: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = loginHint)]
-    pub fn login_hint(this: &RedirectRequest) -> String;
+    pub fn login_hint(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = loginHint)]
     pub fn set_login_hint(this: &RedirectRequest, login_hint: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = domainHint)]
-    pub fn domain_hint(this: &RedirectRequest) -> String;
+    pub fn domain_hint(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = domainHint)]
     pub fn set_domain_hint(this: &RedirectRequest, domain_hint: &str);
 
-    // Hashmap<&str, &str>
-    #[cfg(test)]
+    // Record<string, string>
     #[wasm_bindgen(method, getter = extraQueryParameters)]
-    pub fn extra_query_parameters(this: &RedirectRequest) -> Object;
+    pub fn extra_query_parameters(this: &RedirectRequest) -> Option<Object>;
 
     #[wasm_bindgen(method, setter = extraQueryParameters)]
-    pub fn set_extra_query_parameters(this: &RedirectRequest, claims: Map);
+    pub fn set_extra_query_parameters(this: &RedirectRequest, extra_query_parameters: Object);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn claims(this: &RedirectRequest) -> String;
+    pub fn claims(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_claims(this: &RedirectRequest, claims: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
-    pub fn nonce(this: &RedirectRequest) -> String;
+    pub fn nonce(this: &RedirectRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_nonce(this: &RedirectRequest, nonce: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = redirectStartPage)]
     pub fn redirect_start_page(this: &RedirectRequest) -> Option<String>;
 
@@ -433,42 +431,144 @@ extern "C" {
     #[wasm_bindgen(constructor)]
     pub fn new(scopes: &Array, account: AccountInfo) -> SilentRequest;
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     pub fn scopes(request: &SilentRequest) -> Array;
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     pub fn account(request: &SilentRequest) -> AccountInfo;
 
     #[wasm_bindgen(method, setter)]
     pub fn set_authority(request: &SilentRequest, authority: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter)]
     pub fn authority(request: &SilentRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = correlationId)]
     pub fn set_correlation_id(request: &SilentRequest, correlation_id: &str);
 
-    #[cfg(test)]
+    #[wasm_bindgen(method, getter = authenticationScheme)]
+    pub fn authentication_scheme(request: &SilentRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = authenticationScheme)]
+    pub fn set_authentication_scheme(request: &SilentRequest, authentication_scheme: &str);
+
     #[wasm_bindgen(method, getter = correlationId)]
     pub fn correlation_id(request: &SilentRequest) -> Option<String>;
 
     #[wasm_bindgen(method, setter = forceRefresh)]
     pub fn set_force_refresh(request: &SilentRequest, force_refresh: bool);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = forceRefresh)]
     pub fn force_refresh(request: &SilentRequest) -> Option<bool>;
 
     #[wasm_bindgen(method, setter = redirectUri)]
     pub fn set_redirect_uri(request: &SilentRequest, redirect_uri: &str);
 
-    #[cfg(test)]
     #[wasm_bindgen(method, getter = redirectUri)]
     pub fn redirect_uri(request: &SilentRequest) -> Option<String>;
 
+    #[wasm_bindgen(method, setter)]
+    pub fn set_claims(request: &SilentRequest, claims: &str);
+
+    #[wasm_bindgen(method, getter)]
+    pub fn claims(request: &SilentRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = cacheLookupPolicy)]
+    pub fn set_cache_lookup_policy(request: &SilentRequest, cache_lookup_policy: u32);
+
+    #[wasm_bindgen(method, getter = cacheLookupPolicy)]
+    pub fn cache_lookup_policy(request: &SilentRequest) -> Option<u32>;
+
+    #[wasm_bindgen(method, setter = tokenQueryParameters)]
+    pub fn set_token_query_parameters(request: &SilentRequest, token_query_parameters: Object);
+
+    #[wasm_bindgen(method, getter = tokenQueryParameters)]
+    pub fn token_query_parameters(request: &SilentRequest) -> Option<Object>;
+
+    #[wasm_bindgen(method, setter = extraQueryParameters)]
+    pub fn set_extra_query_parameters(request: &SilentRequest, extra_query_parameters: Object);
+
+    #[wasm_bindgen(method, getter = extraQueryParameters)]
+    pub fn extra_query_parameters(request: &SilentRequest) -> Option<Object>;
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_state(request: &SilentRequest, state: &str);
+
+    #[wasm_bindgen(method, getter)]
+    pub fn state(request: &SilentRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = refreshTokenExpirationOffsetSeconds)]
+    pub fn set_refresh_token_expiration_offset_seconds(request: &SilentRequest, offset: u32);
+
+    #[wasm_bindgen(method, getter = refreshTokenExpirationOffsetSeconds)]
+    pub fn refresh_token_expiration_offset_seconds(request: &SilentRequest) -> Option<u32>;
+
+    #[wasm_bindgen(method, setter = resourceRequestMethod)]
+    pub fn set_resource_request_method(request: &SilentRequest, resource_request_method: &str);
+
+    #[wasm_bindgen(method, getter = resourceRequestMethod)]
+    pub fn resource_request_method(request: &SilentRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = resourceRequestUri)]
+    pub fn set_resource_request_uri(request: &SilentRequest, resource_request_uri: &str);
+
+    #[wasm_bindgen(method, getter = resourceRequestUri)]
+    pub fn resource_request_uri(request: &SilentRequest) -> Option<String>;
+
+    // file://./..//node_modules/@azure/msal-browser/dist/request/AuthorizationCodeRequest.d.ts
+    pub type AuthorizationCodeRequest;
+
+    #[wasm_bindgen(constructor)]
+    pub fn new(scopes: &Array) -> AuthorizationCodeRequest;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn scopes(request: &AuthorizationCodeRequest) -> Option<Array>;
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_authority(request: &AuthorizationCodeRequest, authority: &str);
+
+    #[wasm_bindgen(method, getter)]
+    pub fn authority(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = correlationId)]
+    pub fn set_correlation_id(request: &AuthorizationCodeRequest, correlation_id: &str);
+
+    #[wasm_bindgen(method, getter = authenticationScheme)]
+    pub fn authentication_scheme(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = authenticationScheme)]
+    pub fn set_authentication_scheme(
+        request: &AuthorizationCodeRequest,
+        authentication_scheme: &str,
+    );
+
+    #[wasm_bindgen(method, getter = correlationId)]
+    pub fn correlation_id(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_code(request: &AuthorizationCodeRequest, code: &str);
+
+    #[wasm_bindgen(method, getter)]
+    pub fn code(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = nativeAccountId)]
+    pub fn set_native_account_id(request: &AuthorizationCodeRequest, native_account_id: &str);
+
+    #[wasm_bindgen(method, getter = nativeAccountId)]
+    pub fn native_account_id(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter = codeVerifier)]
+    pub fn set_code_verifier(request: &AuthorizationCodeRequest, code_verifier: &str);
+
+    #[wasm_bindgen(method, getter = codeVerifier)]
+    pub fn code_verifier(request: &AuthorizationCodeRequest) -> Option<String>;
+
+    #[wasm_bindgen(method, setter)]
+    pub fn set_claims(request: &AuthorizationCodeRequest, claims: &str);
+
+    #[wasm_bindgen(method, getter)]
+    pub fn claims(request: &AuthorizationCodeRequest) -> Option<String>;
+
     pub type LoggerOptions;
 
     #[wasm_bindgen(constructor)]
@@ -529,11 +629,18 @@ extern "C" {
 
     // In the ts file this is marked as a Promise<void> and should not be awaited since navigating away
     #[wasm_bindgen(method, js_name = loginRedirect)]
-    pub fn login_redirect(this: &PublicClientApplication, request: AuthorizationUrlRequest);
+    pub fn login_redirect(this: &PublicClientApplication, request: RedirectRequest);
 
     #[wasm_bindgen(method)]
     pub fn logout(this: &PublicClientApplication, request: EndSessionRequest);
 
+    // Resolves without navigating if `onRedirectNavigate` returns false
+    #[wasm_bindgen(method, js_name = logoutRedirect, catch)]
+    pub async fn logout_redirect(
+        this: &PublicClientApplication,
+        request: EndSessionRequest,
+    ) -> Result<JsValue, JsValue>;
+
     // returns [AccountInfo]
     #[wasm_bindgen(method, js_name = getAllAccounts)]
     pub fn get_all_accounts(this: &PublicClientApplication) -> Option<Array>;
@@ -564,6 +671,21 @@ extern "C" {
         request: SilentRequest,
     ) -> Result<JsValue, JsValue>;
 
+    // The same as `acquire_token_silent`, but the promise can be awaited by more than one caller
+    #[wasm_bindgen(method, js_name = acquireTokenSilent)]
+    pub fn acquire_token_silent_promise(
+        this: &PublicClientApplication,
+        request: SilentRequest,
+    ) -> Promise;
+
+    // returns an AuthenticationResult
+    // Hybrid SPA flow: redeems a `spa_code` handed over by the server
+    #[wasm_bindgen(method, js_name = acquireTokenByCode, catch)]
+    pub async fn acquire_token_by_code(
+        this: &PublicClientApplication,
+        request: AuthorizationCodeRequest,
+    ) -> Result<JsValue, JsValue>;
+
     // In the ts file this is marked as a Promise<void> and should not be awaited since navigating away
     #[wasm_bindgen(method, js_name = acquireTokenRedirect)]
     pub fn acquire_token_redirect(this: &PublicClientApplication, request: RedirectRequest);
@@ -609,10 +731,10 @@ extern "C" {
     pub fn from_cache(this: &AuthenticationResult) -> bool;
 
     #[wasm_bindgen(method, getter = expiresOn)]
-    pub fn expires_on(this: &AuthenticationResult) -> Date;
+    pub fn expires_on(this: &AuthenticationResult) -> JsValue;
 
     #[wasm_bindgen(method, getter = extExpiresOn)]
-    pub fn ext_expires_on(this: &AuthenticationResult) -> Option<Date>;
+    pub fn ext_expires_on(this: &AuthenticationResult) -> Option<JsValue>;
 
     #[wasm_bindgen(method, getter)]
     pub fn state(this: &AuthenticationResult) -> Option<String>;
@@ -620,20 +742,34 @@ extern "C" {
     #[wasm_bindgen(method, getter = familyId)]
     pub fn family_id(this: &AuthenticationResult) -> Option<String>;
 
+    #[wasm_bindgen(method, getter = tokenType)]
+    pub fn token_type(this: &AuthenticationResult) -> String;
+
+    #[wasm_bindgen(method, getter = correlationId)]
+    pub fn correlation_id(this: &AuthenticationResult) -> String;
+
+    #[wasm_bindgen(method, getter = cloudGraphHostName)]
+    pub fn cloud_graph_host_name(this: &AuthenticationResult) -> Option<String>;
+
+    #[wasm_bindgen(method, getter = msGraphHost)]
+    pub fn msgraph_host(this: &AuthenticationResult) -> Option<String>;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn code(this: &AuthenticationResult) -> Option<String>;
+
+    #[wasm_bindgen(method, getter = fromNativeBroker)]
+    pub fn from_native_broker(this: &AuthenticationResult) -> Option<bool>;
+
+    #[wasm_bindgen(method, getter = requestId)]
+    pub fn request_id(this: &AuthenticationResult) -> Option<String>;
+
+    #[wasm_bindgen(method, getter = refreshOn)]
+    pub fn refresh_on(this: &AuthenticationResult) -> Option<JsValue>;
+
     // file://./../node_modules/@azure/msal-common/dist/logger/Logger.d.ts
     pub type LogLevel;
 }
 
-impl<'a, T> From<&'a [T]> for AuthorizationUrlRequest
-where
-    T: Into<String> + Clone,
-{
-    fn from(scopes: &'a [T]) -> Self {
-        let js: JsArrayString = scopes.into();
-        AuthorizationUrlRequest::new(&js.into())
-    }
-}
-
 fn array_unchecked_to_vec<JsT, T>(array: Array) -> Vec<T>
 where
     JsT: JsCast + Into<T>,
@@ -694,12 +830,26 @@ impl<'a> From<&'a HashMap<Cow<'a, str>, Cow<'a, str>>> for JsHashMapStrStr<'a> {
     }
 }
 
-impl<'a> From<JsHashMapStrStr<'a>> for Map {
+// msal reads these with `Object.keys` so must be a plain object, not a `Map`
+impl<'a> From<JsHashMapStrStr<'a>> for Object {
     fn from(map: JsHashMapStrStr<'a>) -> Self {
-        let js = Map::new();
+        let js = Object::new();
         for (k, v) in map.0 {
-            js.set(&(**k).into(), &(**v).into());
+            // Can only fail if the target is not an object
+            Reflect::set(&js, &(**k).into(), &(**v).into()).unwrap();
         }
         js
     }
 }
+
+/// Any values that are not strings are dropped
+pub(crate) fn object_to_hash_map<'a>(js: &Object) -> HashMap<Cow<'a, str>, Cow<'a, str>> {
+    let mut hm = HashMap::new();
+    Object::entries(js).for_each(&mut |kv, _, _| {
+        let kv = kv.unchecked_into::<Array>();
+        if let (Some(k), Some(v)) = (kv.get(0).as_string(), kv.get(1).as_string()) {
+            hm.insert(k.into(), v.into());
+        }
+    });
+    hm
+}
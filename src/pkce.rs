@@ -0,0 +1,187 @@
+//! Proof Key for Code Exchange
+//! https://datatracker.ietf.org/doc/html/rfc7636
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// The only method msal accepts
+pub const S256: &str = "S256";
+/// 32 random bytes give a 43 character verifier, the minimum length allowed
+const VERIFIER_BYTES: usize = 32;
+const VERIFIER_MIN_LENGTH: usize = 43;
+const VERIFIER_MAX_LENGTH: usize = 128;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = crypto, js_name = getRandomValues)]
+    fn get_random_values(buf: &mut [u8]);
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PkceError {
+    VerifierLength(usize),
+    VerifierCharacter(char),
+}
+
+impl fmt::Display for PkceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VerifierLength(len) => write!(
+                f,
+                "code verifier must be between {} and {} characters, got {}",
+                VERIFIER_MIN_LENGTH, VERIFIER_MAX_LENGTH, len
+            ),
+            Self::VerifierCharacter(c) => {
+                write!(f, "code verifier contains invalid character `{}`", c)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PkceError {}
+
+impl From<PkceError> for JsValue {
+    fn from(err: PkceError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+/// A code verifier and its S256 challenge. The challenge goes on the
+/// [`AuthorizationUrlRequest`](crate::requests::AuthorizationUrlRequest) and the verifier on the
+/// [`AuthorizationCodeRequest`](crate::requests::AuthorizationCodeRequest) when redeeming the code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PkceCodes {
+    verifier: String,
+    challenge: String,
+}
+
+impl PkceCodes {
+    /// Generates a new random verifier using `crypto.getRandomValues`
+    pub fn new() -> Self {
+        let verifier = generate_code_verifier();
+        Self {
+            challenge: code_challenge(&verifier),
+            verifier,
+        }
+    }
+
+    /// Use an existing verifier, e.g. one kept in session storage across a redirect
+    pub fn from_verifier<T>(verifier: T) -> Result<Self, PkceError>
+    where
+        T: Into<String>,
+    {
+        let verifier = verifier.into();
+        validate_code_verifier(&verifier)?;
+        Ok(Self {
+            challenge: code_challenge(&verifier),
+            verifier,
+        })
+    }
+
+    pub fn verifier(&self) -> &str {
+        &self.verifier
+    }
+
+    pub fn challenge(&self) -> &str {
+        &self.challenge
+    }
+
+    pub fn challenge_method(&self) -> &'static str {
+        S256
+    }
+}
+
+impl Default for PkceCodes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A random 43 character code verifier
+pub fn generate_code_verifier() -> String {
+    let mut bytes = [0; VERIFIER_BYTES];
+    get_random_values(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// `BASE64URL(SHA256(verifier))`
+pub fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Checks the length and that only the unreserved characters
+/// `[A-Z] / [a-z] / [0-9] / "-" / "." / "_" / "~"` are used
+pub fn validate_code_verifier(verifier: &str) -> Result<(), PkceError> {
+    if !(VERIFIER_MIN_LENGTH..=VERIFIER_MAX_LENGTH).contains(&verifier.len()) {
+        return Err(PkceError::VerifierLength(verifier.len()));
+    }
+    match verifier
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')))
+    {
+        Some(c) => Err(PkceError::VerifierCharacter(c)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::requests::{
+        AuthorizationCodeRequest, AuthorizationUrlRequest, GetAuthorizationUrlRequest,
+        SetAuthorizationUrlRequest,
+    };
+    use crate::tests::SCOPE;
+    use wasm_bindgen_test::*;
+
+    // RFC 7636 Appendix B
+    const RFC_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const RFC_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn rfc_challenge() {
+        assert_eq!(code_challenge(RFC_VERIFIER), RFC_CHALLENGE);
+        let pkce = PkceCodes::from_verifier(RFC_VERIFIER).unwrap();
+        assert_eq!(pkce.challenge(), RFC_CHALLENGE);
+        assert_eq!(pkce.challenge_method(), S256);
+    }
+
+    #[test]
+    fn invalid_verifier() {
+        assert_eq!(
+            PkceCodes::from_verifier("short"),
+            Err(PkceError::VerifierLength(5))
+        );
+        assert_eq!(
+            validate_code_verifier(&"a".repeat(129)),
+            Err(PkceError::VerifierLength(129))
+        );
+        assert_eq!(
+            validate_code_verifier(&format!("{}+", &RFC_VERIFIER[1..])),
+            Err(PkceError::VerifierCharacter('+'))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn random_verifier() {
+        let pkce = PkceCodes::new();
+        assert_eq!(pkce.verifier().len(), VERIFIER_MIN_LENGTH);
+        assert_eq!(validate_code_verifier(pkce.verifier()), Ok(()));
+        assert_ne!(pkce, PkceCodes::new());
+    }
+
+    #[wasm_bindgen_test]
+    fn set_pkce_on_requests() {
+        let pkce = PkceCodes::new();
+        let req = AuthorizationUrlRequest::new(&[SCOPE][..]).set_pkce(&pkce);
+        assert_eq!(req.code_challenge(), Some(pkce.challenge()));
+        assert_eq!(req.code_challenge_method(), Some(S256));
+
+        let req = AuthorizationCodeRequest::new(&[SCOPE][..]).set_pkce(&pkce);
+        assert_eq!(req.code_verifier(), Some(pkce.verifier()));
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{
-    acquire_token_silent, msal,
+    acquire_token_by_code, acquire_token_silent, acquire_token_silent_ref, clear_cache, msal,
     msal::Msal,
-    requests::{AuthorizationUrlRequest, SilentRequest},
-    sso_silent, AuthenticationResult, Configuration, PublicClientApplication,
+    requests::{
+        AuthorizationCodeRequest, AuthorizationUrlRequest, GetAuthorizationUrlRequest,
+        SilentRequest,
+    },
+    scopes_to_vec, sso_silent, AccountInfo, AuthenticationResult, AuthenticationResultRef,
+    Configuration, PublicClientApplication,
 };
 use wasm_bindgen::JsValue;
 
@@ -45,7 +49,18 @@ impl PopupApp {
     where
         T: Into<String> + Clone,
     {
-        self.auth.login_popup(scopes.into()).await.map(Into::into)
+        let scopes = scopes_to_vec(scopes);
+        self.login_popup_with_request(&AuthorizationUrlRequest::new(&scopes))
+            .await
+    }
+
+    /// Login with a full request, e.g. to set the `prompt` or `login_hint`
+    pub async fn login_popup_with_request<'a>(
+        &self,
+        request: &'a AuthorizationUrlRequest<'a>,
+    ) -> Result<AuthenticationResult, JsValue> {
+        request.check_response_mode()?;
+        self.auth.login_popup(request.into()).await.map(Into::into)
     }
 
     pub async fn sso_silent<'a>(
@@ -62,10 +77,41 @@ impl PopupApp {
         acquire_token_silent(&self.auth, request).await
     }
 
+    /// As [`acquire_token_silent`](Self::acquire_token_silent) but only converts
+    /// the fields of the result that are used
+    pub async fn acquire_token_silent_ref<'a>(
+        &self,
+        request: &'a SilentRequest<'a>,
+    ) -> Result<AuthenticationResultRef, JsValue> {
+        acquire_token_silent_ref(&self.auth, request).await
+    }
+
+    pub async fn acquire_token_by_code<'a>(
+        &self,
+        request: &'a AuthorizationCodeRequest<'a>,
+    ) -> Result<AuthenticationResult, JsValue> {
+        acquire_token_by_code(&self.auth, request).await
+    }
+
+    /// Removes the account and its tokens from the cache, or everything when `None`. The
+    /// user is still signed in to their identity provider so can sign in again silently.
+    ///
+    /// This is msal's `logoutRedirect` without the navigation, so it fails with
+    /// `interaction_in_progress` while a popup or redirect is pending, marks an interaction
+    /// as in progress until it resolves, and raises `LOGOUT_START` and `LOGOUT_SUCCESS` to
+    /// event callbacks as a logout would. Tokens held by a
+    /// [`TokenManager`](crate::token_manager::TokenManager) are kept, use
+    /// [`TokenManager::clear_cache`](crate::token_manager::TokenManager::clear_cache) to
+    /// clear both.
+    pub async fn clear_cache(&self, account: Option<&AccountInfo>) -> Result<(), JsValue> {
+        clear_cache(&self.auth, account).await
+    }
+
     pub async fn acquire_token_popup<'a>(
         &self,
         request: &'a AuthorizationUrlRequest<'a>,
     ) -> Result<AuthenticationResult, JsValue> {
+        request.check_response_mode()?;
         self.auth
             .acquire_token_popup(request.into())
             .await
@@ -78,7 +124,11 @@ mod tests {
     wasm_bindgen_test_configure!(run_in_browser);
 
     use super::*;
-    use crate::{tests::*, BrowserAuthOptions};
+    use crate::{
+        requests::{ResponseMode, SetAuthorizationUrlRequest},
+        tests::*,
+        BrowserAuthOptions,
+    };
     // use web_sys::console;
     use wasm_bindgen_test::*;
 
@@ -107,6 +157,31 @@ mod tests {
         client_app.login_popup();
     }
 
+    #[wasm_bindgen_test]
+    async fn acquire_token_popup_form_post() {
+        let b = BrowserAuthOptions::new(tests::CLIENT_ID)
+            .set_authority(AUTHORITY)
+            .set_redirect_uri(REDIRECT_URI);
+        let c = Configuration::new(b);
+        let client_app = PopupApp::new(c);
+        let request =
+            AuthorizationUrlRequest::new(&[SCOPE][..]).set_response_mode(ResponseMode::FormPost);
+        assert!(client_app.acquire_token_popup(&request).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn login_popup_form_post() {
+        let b = BrowserAuthOptions::new(tests::CLIENT_ID)
+            .set_authority(AUTHORITY)
+            .set_redirect_uri(REDIRECT_URI);
+        let c = Configuration::new(b);
+        let client_app = PopupApp::new(c);
+        let request = AuthorizationUrlRequest::new(&[SCOPE][..])
+            .set_login_hint(USERNAME)
+            .set_response_mode(ResponseMode::FormPost);
+        assert!(client_app.login_popup_with_request(&request).await.is_err());
+    }
+
     #[wasm_bindgen_test]
     fn can_clone() {
         let b = BrowserAuthOptions::new(tests::CLIENT_ID)
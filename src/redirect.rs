@@ -1,8 +1,12 @@
 use crate::{
-    acquire_token_silent, msal,
+    acquire_token_by_code, acquire_token_silent, acquire_token_silent_ref, clear_cache, msal,
     msal::Msal,
-    requests::{AuthorizationUrlRequest, RedirectRequest, SilentRequest},
-    sso_silent, AuthenticationResult, Configuration, PublicClientApplication,
+    requests::{
+        AuthorizationCodeRequest, AuthorizationUrlRequest, GetAuthorizationUrlRequest,
+        RedirectRequest, SilentRequest,
+    },
+    scopes_to_vec, sso_silent, AccountInfo, AuthenticationResult, AuthenticationResultRef,
+    Configuration, PublicClientApplication,
 };
 use wasm_bindgen::{JsCast, JsValue};
 
@@ -70,13 +74,26 @@ where
     where
         T: Into<String> + Clone,
     {
+        let scopes = scopes_to_vec(scopes);
+        // Can only fail on the response mode, which isn't set
+        let _ = self
+            .login_redirect_with_request(&RedirectRequest::new(&scopes))
+            .await;
+    }
+
+    /// Login with a full request, e.g. to set the `prompt` or `login_hint`
+    pub async fn login_redirect_with_request<'a>(
+        &self,
+        request: &'a RedirectRequest<'a>,
+    ) -> Result<(), JsValue> {
+        request.check_response_mode()?;
         match self.auth.handle_redirect_promise().await {
             Ok(auth_result) => {
                 // AuthenticationResult will be undefined / null if not a redirect
                 // Can't use the 'safe' methods since the type check fails even when valid as is an Object.
                 let auth_res = auth_result.unchecked_into::<msal::AuthenticationResult>();
                 if auth_res.is_undefined() || auth_res.is_null() {
-                    self.auth.login_redirect(scopes.into())
+                    self.auth.login_redirect(request.into())
                 } else {
                     (self.on_redirect_success)(auth_res.into())
                 }
@@ -88,10 +105,16 @@ where
                 // }
             }
         }
+        Ok(())
     }
 
-    pub async fn acquire_token_redirect<'a>(&self, request: &'a RedirectRequest<'a>) {
-        self.auth.acquire_token_redirect(request.into())
+    pub async fn acquire_token_redirect<'a>(
+        &self,
+        request: &'a RedirectRequest<'a>,
+    ) -> Result<(), JsValue> {
+        request.check_response_mode()?;
+        self.auth.acquire_token_redirect(request.into());
+        Ok(())
     }
 
     pub async fn sso_silent<'a>(
@@ -107,6 +130,36 @@ where
     ) -> Result<AuthenticationResult, JsValue> {
         acquire_token_silent(&self.auth, request).await
     }
+
+    /// As [`acquire_token_silent`](Self::acquire_token_silent) but only converts
+    /// the fields of the result that are used
+    pub async fn acquire_token_silent_ref<'a>(
+        &self,
+        request: &'a SilentRequest<'a>,
+    ) -> Result<AuthenticationResultRef, JsValue> {
+        acquire_token_silent_ref(&self.auth, request).await
+    }
+
+    pub async fn acquire_token_by_code<'a>(
+        &self,
+        request: &'a AuthorizationCodeRequest<'a>,
+    ) -> Result<AuthenticationResult, JsValue> {
+        acquire_token_by_code(&self.auth, request).await
+    }
+
+    /// Removes the account and its tokens from the cache, or everything when `None`. The
+    /// user is still signed in to their identity provider so can sign in again silently.
+    ///
+    /// This is msal's `logoutRedirect` without the navigation, so it fails with
+    /// `interaction_in_progress` while a popup or redirect is pending, marks an interaction
+    /// as in progress until it resolves, and raises `LOGOUT_START` and `LOGOUT_SUCCESS` to
+    /// event callbacks as a logout would. Tokens held by a
+    /// [`TokenManager`](crate::token_manager::TokenManager) are kept, use
+    /// [`TokenManager::clear_cache`](crate::token_manager::TokenManager::clear_cache) to
+    /// clear both.
+    pub async fn clear_cache(&self, account: Option<&AccountInfo>) -> Result<(), JsValue> {
+        clear_cache(&self.auth, account).await
+    }
 }
 
 #[cfg(test)]
@@ -114,7 +167,11 @@ mod tests {
     wasm_bindgen_test_configure!(run_in_browser);
 
     use super::*;
-    use crate::{tests::*, BrowserAuthOptions};
+    use crate::{
+        requests::{Prompt, ResponseMode, SetAuthorizationUrlRequest},
+        tests::*,
+        BrowserAuthOptions,
+    };
     use wasm_bindgen_test::*;
 
     #[allow(unused_must_use)]
@@ -127,4 +184,20 @@ mod tests {
         let client_app = RedirectApp::new(config, |_| ());
         client_app.login_redirect();
     }
+
+    #[wasm_bindgen_test]
+    async fn login_redirect_form_post() {
+        let b = BrowserAuthOptions::new(tests::CLIENT_ID)
+            .set_authority(AUTHORITY)
+            .set_redirect_uri(REDIRECT_URI);
+        let config = Configuration::new(b);
+        let client_app = RedirectApp::new(config, |_| ());
+        let request = RedirectRequest::new(&[SCOPE][..])
+            .set_prompt(Prompt::SelectAccount)
+            .set_response_mode(ResponseMode::FormPost);
+        assert!(client_app
+            .login_redirect_with_request(&request)
+            .await
+            .is_err());
+    }
 }
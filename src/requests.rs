@@ -1,8 +1,65 @@
-use crate::{msal, AccountInfo};
-use msal::{JsArrayString, JsHashMapStrStr};
-use std::{borrow::Cow, collections::HashMap};
+use crate::{msal, pkce::PkceCodes, AccountInfo};
+use js_sys::Array;
+use msal::{object_to_hash_map, JsArrayString, JsHashMapStrStr};
+use std::{borrow::Cow, collections::HashMap, fmt, str::FromStr};
+#[cfg(feature = "redirect")]
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+
+/// Errors found when converting a request, before it is handed to msal
+#[derive(Clone, Debug, PartialEq)]
+pub enum RequestError {
+    UnsupportedResponseMode(ResponseMode),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::UnsupportedResponseMode(response_mode) => write!(
+                f,
+                "response mode `{}` is not supported in the browser, use `{}` or `{}`",
+                response_mode,
+                ResponseMode::Fragment,
+                ResponseMode::Query
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
 
-#[derive(Clone)]
+// So can be returned alongside the errors thrown by msal
+impl From<RequestError> for JsValue {
+    fn from(error: RequestError) -> Self {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+/// https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-browser/src/utils/BrowserConstants.ts
+#[derive(Clone, Debug, PartialEq)]
+pub enum InteractionType {
+    Redirect,
+    Popup,
+    Silent,
+}
+
+impl InteractionType {
+    fn as_str(&self) -> &str {
+        match &self {
+            InteractionType::Redirect => "redirect",
+            InteractionType::Popup => "popup",
+            InteractionType::Silent => "silent",
+        }
+    }
+}
+
+impl fmt::Display for InteractionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum ResponseMode {
     Query,
     Fragment,
@@ -17,13 +74,79 @@ impl ResponseMode {
             ResponseMode::FormPost => "form_post",
         }
     }
+
+    /// `form_post` sends the response to the server, so can never be used by a SPA whatever
+    /// the interaction type
+    fn check(&self) -> Result<(), RequestError> {
+        match self {
+            ResponseMode::FormPost => Err(RequestError::UnsupportedResponseMode(self.clone())),
+            ResponseMode::Query | ResponseMode::Fragment => Ok(()),
+        }
+    }
 }
 
-#[derive(Clone)]
+impl fmt::Display for ResponseMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ResponseMode {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "query" => Ok(ResponseMode::Query),
+            "fragment" => Ok(ResponseMode::Fragment),
+            "form_post" => Ok(ResponseMode::FormPost),
+            _ => Err(format!("{} is not a valid response mode", value)),
+        }
+    }
+}
+
+/// https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-common/src/utils/Constants.ts
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AuthenticationScheme {
+    #[default]
+    Bearer,
+    /// Proof of possession, the token is bound to the request
+    Pop,
+    SshCert,
+}
+
+impl AuthenticationScheme {
+    pub(crate) fn as_str(&self) -> &str {
+        match &self {
+            AuthenticationScheme::Bearer => "Bearer",
+            AuthenticationScheme::Pop => "pop",
+            AuthenticationScheme::SshCert => "ssh-cert",
+        }
+    }
+}
+
+impl fmt::Display for AuthenticationScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuthenticationScheme {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Bearer" => Ok(AuthenticationScheme::Bearer),
+            "pop" => Ok(AuthenticationScheme::Pop),
+            "ssh-cert" => Ok(AuthenticationScheme::SshCert),
+            _ => Err(format!("{} is not a valid authentication scheme", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct BaseAuthRequest<'a> {
     scopes: Vec<Cow<'a, str>>, // TODO: Can this be a slice?
     authority: Option<Cow<'a, str>>,
     correlation_id: Option<Cow<'a, str>>,
+    authentication_scheme: Option<AuthenticationScheme>,
 }
 
 /// No scopes required since all the request constructors require Scopes
@@ -32,6 +155,7 @@ struct IterBaseAuthRequest<'a, T> {
     destination: &'a T,
     authority: &'a dyn Fn(&T, &Cow<'a, str>),
     correlation_id: &'a dyn Fn(&T, &Cow<'a, str>),
+    authentication_scheme: &'a dyn Fn(&T, &AuthenticationScheme),
 }
 
 impl<'a, T> IterBaseAuthRequest<'a, T> {
@@ -42,6 +166,9 @@ impl<'a, T> IterBaseAuthRequest<'a, T> {
         if let Some(v) = &self.base_auth_request.correlation_id {
             (self.correlation_id)(self.destination, v)
         }
+        if let Some(v) = &self.base_auth_request.authentication_scheme {
+            (self.authentication_scheme)(self.destination, v)
+        }
     }
 }
 
@@ -65,10 +192,69 @@ pub trait SetBaseAuthRequest<'a> {
         self.base_request().correlation_id = Some(correlation_id.into());
         self
     }
+
+    fn set_authentication_scheme(mut self, authentication_scheme: AuthenticationScheme) -> Self
+    where
+        Self: std::marker::Sized,
+    {
+        self.base_request().authentication_scheme = Some(authentication_scheme);
+        self
+    }
+}
+
+pub trait GetBaseAuthRequest {
+    fn get_base_request(&self) -> &BaseAuthRequest<'_>;
+
+    fn scopes(&self) -> &[Cow<'_, str>] {
+        &self.get_base_request().scopes
+    }
+
+    fn authority(&self) -> Option<&str> {
+        self.get_base_request().authority.as_deref()
+    }
+
+    fn correlation_id(&self) -> Option<&str> {
+        self.get_base_request().correlation_id.as_deref()
+    }
+
+    fn authentication_scheme(&self) -> Option<&AuthenticationScheme> {
+        self.get_base_request().authentication_scheme.as_ref()
+    }
+}
+
+/// Clones any borrowed data so the value is no longer tied to the lifetime of the input
+fn owned(value: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(value.into_owned())
+}
+
+fn owned_vec(values: Vec<Cow<'_, str>>) -> Vec<Cow<'static, str>> {
+    values.into_iter().map(owned).collect()
+}
+
+fn owned_map(
+    map: HashMap<Cow<'_, str>, Cow<'_, str>>,
+) -> HashMap<Cow<'static, str>, Cow<'static, str>> {
+    map.into_iter().map(|(k, v)| (owned(k), owned(v))).collect()
+}
+
+fn cow_map<'a, T>(map: HashMap<T, T>) -> HashMap<Cow<'a, str>, Cow<'a, str>>
+where
+    T: Into<Cow<'a, str>>,
+{
+    map.into_iter().map(|(k, v)| (k.into(), v.into())).collect()
 }
 
 impl<'a> BaseAuthRequest<'a> {
-    fn new<T>(scopes: &'a [T]) -> Self
+    fn into_owned(self) -> BaseAuthRequest<'static> {
+        BaseAuthRequest {
+            scopes: owned_vec(self.scopes),
+            authority: self.authority.map(owned),
+            correlation_id: self.correlation_id.map(owned),
+            authentication_scheme: self.authentication_scheme,
+        }
+    }
+
+    fn new<T>(scopes: &[T]) -> Self
     where
         T: Clone + Into<Cow<'a, str>>,
     {
@@ -76,16 +262,36 @@ impl<'a> BaseAuthRequest<'a> {
             scopes: scopes.iter().cloned().map(Into::into).collect(),
             authority: None,
             correlation_id: None,
+            authentication_scheme: None,
+        }
+    }
+
+    fn from_js(
+        scopes: Option<Array>,
+        authority: Option<String>,
+        correlation_id: Option<String>,
+        authentication_scheme: Option<String>,
+    ) -> Self {
+        Self {
+            scopes: scopes
+                .map(|v| JsArrayString::from(v).into())
+                .unwrap_or_default(),
+            authority: authority.map(Cow::from),
+            correlation_id: correlation_id.map(Cow::from),
+            authentication_scheme: authentication_scheme.and_then(|v| v.parse().ok()),
         }
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Prompt {
     Login,
     None,
     Consent,
     SelectAccount,
+    /// Self-service sign-up, the user is shown the sign-up experience
+    Create,
+    NoSession,
 }
 
 impl Prompt {
@@ -95,10 +301,34 @@ impl Prompt {
             Prompt::None => "none",
             Prompt::Consent => "consent",
             Prompt::SelectAccount => "select_account",
+            Prompt::Create => "create",
+            Prompt::NoSession => "no_session",
+        }
+    }
+}
+
+impl fmt::Display for Prompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Prompt {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "login" => Ok(Prompt::Login),
+            "none" => Ok(Prompt::None),
+            "consent" => Ok(Prompt::Consent),
+            "select_account" => Ok(Prompt::SelectAccount),
+            "create" => Ok(Prompt::Create),
+            "no_session" => Ok(Prompt::NoSession),
+            _ => Err(format!("{} is not a valid prompt", value)),
         }
     }
 }
-#[derive(Clone)]
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct AuthorizationUrlRequest<'a> {
     base_request: BaseAuthRequest<'a>,
     redirect_uri: Option<Cow<'a, str>>,
@@ -116,7 +346,7 @@ pub struct AuthorizationUrlRequest<'a> {
 }
 
 impl<'a> AuthorizationUrlRequest<'a> {
-    pub fn new<T>(scopes: &'a [T]) -> Self
+    pub fn new<T>(scopes: &[T]) -> Self
     where
         T: Clone + Into<Cow<'a, str>>,
     {
@@ -142,6 +372,7 @@ impl<'a> AuthorizationUrlRequest<'a> {
             scopes: Vec::new(),
             authority: None,
             correlation_id: None,
+            authentication_scheme: None,
         };
         Self {
             base_request,
@@ -159,6 +390,25 @@ impl<'a> AuthorizationUrlRequest<'a> {
             nonce: None,
         }
     }
+
+    /// Converts to a request that owns all its data, so can be stored or moved into a future
+    pub fn into_owned(self) -> AuthorizationUrlRequest<'static> {
+        AuthorizationUrlRequest {
+            base_request: self.base_request.into_owned(),
+            redirect_uri: self.redirect_uri.map(owned),
+            extra_scopes_to_consent: self.extra_scopes_to_consent.map(owned_vec),
+            response_mode: self.response_mode,
+            code_challenge: self.code_challenge.map(owned),
+            code_challenge_method: self.code_challenge_method.map(owned),
+            state: self.state.map(owned),
+            prompt: self.prompt,
+            login_hint: self.login_hint.map(owned),
+            domain_hint: self.domain_hint.map(owned),
+            extra_query_parameters: self.extra_query_parameters.map(owned_map),
+            claims: self.claims.map(owned),
+            nonce: self.nonce.map(owned),
+        }
+    }
 }
 
 struct IterAuthorizationUrlRequest<'a, T> {
@@ -233,7 +483,7 @@ pub trait SetAuthorizationUrlRequest<'a> {
         self
     }
 
-    fn set_extra_scopes_to_consent<T>(mut self, extra_scopes_to_consent: &'a [T]) -> Self
+    fn set_extra_scopes_to_consent<T>(mut self, extra_scopes_to_consent: &[T]) -> Self
     where
         T: Into<Cow<'a, str>> + Clone,
         Self: std::marker::Sized,
@@ -274,6 +524,17 @@ pub trait SetAuthorizationUrlRequest<'a> {
         self
     }
 
+    /// Sets both the code challenge and the challenge method
+    fn set_pkce(mut self, pkce: &'a PkceCodes) -> Self
+    where
+        Self: std::marker::Sized,
+    {
+        let req = self.auth_request();
+        req.code_challenge = Some(pkce.challenge().into());
+        req.code_challenge_method = Some(pkce.challenge_method().into());
+        self
+    }
+
     fn set_state<T>(mut self, state: T) -> Self
     where
         T: Into<Cow<'a, str>>,
@@ -283,7 +544,7 @@ pub trait SetAuthorizationUrlRequest<'a> {
         self
     }
 
-    fn set_prompt<T>(mut self, prompt: Prompt) -> Self
+    fn set_prompt(mut self, prompt: Prompt) -> Self
     where
         Self: std::marker::Sized,
     {
@@ -314,14 +575,11 @@ pub trait SetAuthorizationUrlRequest<'a> {
         T: Into<Cow<'a, str>> + Clone,
         Self: std::marker::Sized,
     {
-        let mut hm: HashMap<Cow<'a, str>, Cow<'a, str>> = HashMap::new();
-        for (k, v) in extra_query_parameters {
-            hm.insert(k.into(), v.into());
-        }
-        self.auth_request().extra_query_parameters = Some(hm);
+        self.auth_request().extra_query_parameters = Some(cow_map(extra_query_parameters));
         self
     }
 
+    /// Accepts a json string or a [`ClaimsRequest`](crate::claims_request::ClaimsRequest)
     fn set_claims<T>(mut self, claims: T) -> Self
     where
         T: Into<Cow<'a, str>>,
@@ -353,8 +611,77 @@ impl<'a> SetBaseAuthRequest<'a> for AuthorizationUrlRequest<'a> {
     }
 }
 
-impl<'a> From<&'a AuthorizationUrlRequest<'a>> for msal::AuthorizationUrlRequest {
-    fn from(request: &'a AuthorizationUrlRequest<'a>) -> Self {
+pub trait GetAuthorizationUrlRequest {
+    fn get_auth_request(&self) -> &AuthorizationUrlRequest<'_>;
+
+    fn redirect_uri(&self) -> Option<&str> {
+        self.get_auth_request().redirect_uri.as_deref()
+    }
+
+    fn extra_scopes_to_consent(&self) -> Option<&[Cow<'_, str>]> {
+        self.get_auth_request().extra_scopes_to_consent.as_deref()
+    }
+
+    fn response_mode(&self) -> Option<&ResponseMode> {
+        self.get_auth_request().response_mode.as_ref()
+    }
+
+    fn code_challenge(&self) -> Option<&str> {
+        self.get_auth_request().code_challenge.as_deref()
+    }
+
+    fn code_challenge_method(&self) -> Option<&str> {
+        self.get_auth_request().code_challenge_method.as_deref()
+    }
+
+    fn state(&self) -> Option<&str> {
+        self.get_auth_request().state.as_deref()
+    }
+
+    fn prompt(&self) -> Option<&Prompt> {
+        self.get_auth_request().prompt.as_ref()
+    }
+
+    fn login_hint(&self) -> Option<&str> {
+        self.get_auth_request().login_hint.as_deref()
+    }
+
+    fn domain_hint(&self) -> Option<&str> {
+        self.get_auth_request().domain_hint.as_deref()
+    }
+
+    fn extra_query_parameters(&self) -> Option<&HashMap<Cow<'_, str>, Cow<'_, str>>> {
+        self.get_auth_request().extra_query_parameters.as_ref()
+    }
+
+    fn claims(&self) -> Option<&str> {
+        self.get_auth_request().claims.as_deref()
+    }
+
+    fn nonce(&self) -> Option<&str> {
+        self.get_auth_request().nonce.as_deref()
+    }
+
+    /// msal-browser only fails once the request is sent, so this is checked before converting
+    fn check_response_mode(&self) -> Result<(), RequestError> {
+        self.response_mode().map_or(Ok(()), ResponseMode::check)
+    }
+}
+
+impl<'a> GetAuthorizationUrlRequest for AuthorizationUrlRequest<'a> {
+    fn get_auth_request(&self) -> &AuthorizationUrlRequest<'_> {
+        self
+    }
+}
+
+impl<'a> GetBaseAuthRequest for AuthorizationUrlRequest<'a> {
+    fn get_base_request(&self) -> &BaseAuthRequest<'_> {
+        &self.base_request
+    }
+}
+
+impl<'a, 'b> From<&'b AuthorizationUrlRequest<'a>> for msal::AuthorizationUrlRequest {
+    fn from(request: &'b AuthorizationUrlRequest<'a>) -> Self {
         let js = msal::AuthorizationUrlRequest::new(
             &JsArrayString::from(&request.base_request.scopes).into(),
         );
@@ -367,6 +694,7 @@ impl<'a> From<&'a AuthorizationUrlRequest<'a>> for msal::AuthorizationUrlRequest
                 destination: &js,
                 authority: &|js, v| js.set_authority(v),
                 correlation_id: &|js, v| js.set_correlation_id(v),
+                authentication_scheme: &|js, v| js.set_authentication_scheme(v.as_str()),
             },
             redirect_uri: &|js, v| js.set_redirect_uri(v),
             extra_scopes_to_consent: &|js, v| {
@@ -390,8 +718,35 @@ impl<'a> From<&'a AuthorizationUrlRequest<'a>> for msal::AuthorizationUrlRequest
     }
 }
 
+impl<'a> From<msal::AuthorizationUrlRequest> for AuthorizationUrlRequest<'a> {
+    fn from(js: msal::AuthorizationUrlRequest) -> Self {
+        Self {
+            base_request: BaseAuthRequest::from_js(
+                js.scopes(),
+                js.authority(),
+                js.correlation_id(),
+                js.authentication_scheme(),
+            ),
+            redirect_uri: js.redirect_uri().map(Cow::from),
+            extra_scopes_to_consent: js
+                .extra_scopes_to_consent()
+                .map(|v| JsArrayString::from(v).into()),
+            response_mode: js.response_mode().and_then(|v| v.parse().ok()),
+            code_challenge: js.code_challenge().map(Cow::from),
+            code_challenge_method: js.code_challenge_method().map(Cow::from),
+            state: js.state().map(Cow::from),
+            prompt: js.prompt().and_then(|v| v.parse().ok()),
+            login_hint: js.login_hint().map(Cow::from),
+            domain_hint: js.domain_hint().map(Cow::from),
+            extra_query_parameters: js.extra_query_parameters().map(|v| object_to_hash_map(&v)),
+            claims: js.claims().map(Cow::from),
+            nonce: js.nonce().map(Cow::from),
+        }
+    }
+}
+
 #[cfg(feature = "redirect")]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RedirectRequest<'a> {
     auth_url_req: AuthorizationUrlRequest<'a>,
     redirect_start_page: Option<Cow<'a, str>>,
@@ -399,7 +754,7 @@ pub struct RedirectRequest<'a> {
 
 #[cfg(feature = "redirect")]
 impl<'a> RedirectRequest<'a> {
-    pub fn new<T>(scopes: &'a [T]) -> Self
+    pub fn new<T>(scopes: &[T]) -> Self
     where
         T: Clone + Into<Cow<'a, str>>,
     {
@@ -408,6 +763,26 @@ impl<'a> RedirectRequest<'a> {
             redirect_start_page: None,
         }
     }
+
+    /// See [`AuthorizationUrlRequest::into_owned`]
+    pub fn into_owned(self) -> RedirectRequest<'static> {
+        RedirectRequest {
+            auth_url_req: self.auth_url_req.into_owned(),
+            redirect_start_page: self.redirect_start_page.map(owned),
+        }
+    }
+
+    pub fn set_redirect_start_page<T>(mut self, redirect_start_page: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.redirect_start_page = Some(redirect_start_page.into());
+        self
+    }
+
+    pub fn redirect_start_page(&self) -> Option<&str> {
+        self.redirect_start_page.as_deref()
+    }
 }
 
 #[cfg(feature = "redirect")]
@@ -425,8 +800,33 @@ impl<'a> SetAuthorizationUrlRequest<'a> for RedirectRequest<'a> {
 }
 
 #[cfg(feature = "redirect")]
-impl<'a> From<&'a RedirectRequest<'a>> for msal::RedirectRequest {
-    fn from(request: &'a RedirectRequest<'a>) -> Self {
+impl<'a> GetBaseAuthRequest for RedirectRequest<'a> {
+    fn get_base_request(&self) -> &BaseAuthRequest<'_> {
+        &self.auth_url_req.base_request
+    }
+}
+
+#[cfg(feature = "redirect")]
+impl<'a> GetAuthorizationUrlRequest for RedirectRequest<'a> {
+    fn get_auth_request(&self) -> &AuthorizationUrlRequest<'_> {
+        &self.auth_url_req
+    }
+}
+
+#[cfg(feature = "redirect")]
+impl<'a> From<msal::RedirectRequest> for RedirectRequest<'a> {
+    fn from(js: msal::RedirectRequest) -> Self {
+        Self {
+            redirect_start_page: js.redirect_start_page().map(Cow::from),
+            // Same properties, since a RedirectRequest extends an AuthorizationUrlRequest
+            auth_url_req: js.unchecked_into::<msal::AuthorizationUrlRequest>().into(),
+        }
+    }
+}
+
+#[cfg(feature = "redirect")]
+impl<'a, 'b> From<&'b RedirectRequest<'a>> for msal::RedirectRequest {
+    fn from(request: &'b RedirectRequest<'a>) -> Self {
         let js = msal::RedirectRequest::new(
             &JsArrayString::from(&request.auth_url_req.base_request.scopes).into(),
         );
@@ -439,6 +839,7 @@ impl<'a> From<&'a RedirectRequest<'a>> for msal::RedirectRequest {
                 destination: &js,
                 authority: &|js, v| js.set_authority(v),
                 correlation_id: &|js, v| js.set_correlation_id(v),
+                authentication_scheme: &|js, v| js.set_authentication_scheme(v.as_str()),
             },
             redirect_uri: &|js, v| js.set_redirect_uri(v),
             extra_scopes_to_consent: &|js, v| {
@@ -466,12 +867,66 @@ impl<'a> From<&'a RedirectRequest<'a>> for msal::RedirectRequest {
     }
 }
 
-#[derive(Clone)]
+/// Where `acquire_token_silent` looks for tokens, and when it goes to the network
+/// https://github.com/AzureAD/microsoft-authentication-library-for-js/blob/dev/lib/msal-browser/src/utils/BrowserConstants.ts
+#[derive(Clone, Debug, PartialEq)]
+pub enum CacheLookupPolicy {
+    /// Cached access token, then refresh token, then the network
+    Default,
+    /// Only the cached access token, never makes a network call
+    AccessToken,
+    /// Cached access token, then refresh token, no network renewal of the refresh token
+    AccessTokenAndRefreshToken,
+    /// Always uses the cached refresh token
+    RefreshToken,
+    /// Always refreshes, the same as `force_refresh`
+    RefreshTokenAndNetwork,
+    /// Skips the cache, fails if 3rd party cookies are blocked
+    Skip,
+}
+
+impl CacheLookupPolicy {
+    fn as_u32(&self) -> u32 {
+        match self {
+            CacheLookupPolicy::Default => 0,
+            CacheLookupPolicy::AccessToken => 1,
+            CacheLookupPolicy::AccessTokenAndRefreshToken => 2,
+            CacheLookupPolicy::RefreshToken => 3,
+            CacheLookupPolicy::RefreshTokenAndNetwork => 4,
+            CacheLookupPolicy::Skip => 5,
+        }
+    }
+}
+
+impl TryFrom<u32> for CacheLookupPolicy {
+    type Error = String;
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CacheLookupPolicy::Default),
+            1 => Ok(CacheLookupPolicy::AccessToken),
+            2 => Ok(CacheLookupPolicy::AccessTokenAndRefreshToken),
+            3 => Ok(CacheLookupPolicy::RefreshToken),
+            4 => Ok(CacheLookupPolicy::RefreshTokenAndNetwork),
+            5 => Ok(CacheLookupPolicy::Skip),
+            _ => Err(format!("{} is not a valid cache lookup policy", value)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct SilentRequest<'a> {
     base_request: BaseAuthRequest<'a>,
-    account: &'a AccountInfo,
+    account: Cow<'a, AccountInfo>,
     force_refresh: Option<bool>,
     redirect_uri: Option<Cow<'a, str>>,
+    claims: Option<Cow<'a, str>>,
+    cache_lookup_policy: Option<CacheLookupPolicy>,
+    token_query_parameters: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    extra_query_parameters: Option<HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    state: Option<Cow<'a, str>>,
+    refresh_token_expiration_offset_seconds: Option<u32>,
+    resource_request_method: Option<Cow<'a, str>>,
+    resource_request_uri: Option<Cow<'a, str>>,
 }
 
 impl<'a> SetBaseAuthRequest<'a> for SilentRequest<'a> {
@@ -480,16 +935,59 @@ impl<'a> SetBaseAuthRequest<'a> for SilentRequest<'a> {
     }
 }
 
+impl<'a> GetBaseAuthRequest for SilentRequest<'a> {
+    fn get_base_request(&self) -> &BaseAuthRequest<'_> {
+        &self.base_request
+    }
+}
+
 impl<'a> SilentRequest<'a> {
-    pub fn new<T>(scopes: &'a [T], account_info: &'a AccountInfo) -> Self
+    pub fn new<T, A>(scopes: &[T], account_info: A) -> Self
     where
         T: Clone + Into<Cow<'a, str>>,
+        A: Into<Cow<'a, AccountInfo>>,
     {
         Self {
             base_request: BaseAuthRequest::new(scopes),
-            account: account_info,
+            account: account_info.into(),
             force_refresh: None,
             redirect_uri: None,
+            claims: None,
+            cache_lookup_policy: None,
+            token_query_parameters: None,
+            extra_query_parameters: None,
+            state: None,
+            refresh_token_expiration_offset_seconds: None,
+            resource_request_method: None,
+            resource_request_uri: None,
+        }
+    }
+
+    /// See [`AuthorizationUrlRequest::into_owned`]
+    pub fn into_owned(self) -> SilentRequest<'static> {
+        SilentRequest {
+            base_request: self.base_request.into_owned(),
+            account: Cow::Owned(self.account.into_owned()),
+            force_refresh: self.force_refresh,
+            redirect_uri: self.redirect_uri.map(owned),
+            claims: self.claims.map(owned),
+            cache_lookup_policy: self.cache_lookup_policy,
+            token_query_parameters: self.token_query_parameters.map(owned_map),
+            extra_query_parameters: self.extra_query_parameters.map(owned_map),
+            state: self.state.map(owned),
+            refresh_token_expiration_offset_seconds: self.refresh_token_expiration_offset_seconds,
+            resource_request_method: self.resource_request_method.map(owned),
+            resource_request_uri: self.resource_request_uri.map(owned),
+        }
+    }
+
+    // Without the options that only apply to this call, to repeat it later
+    pub(crate) fn to_refresh_template(&self) -> SilentRequest<'static> {
+        SilentRequest {
+            force_refresh: None,
+            claims: None,
+            cache_lookup_policy: None,
+            ..self.clone().into_owned()
         }
     }
 
@@ -505,13 +1003,152 @@ impl<'a> SilentRequest<'a> {
         self.redirect_uri = Some(redirect_uri.into());
         self
     }
+
+    /// Accepts a json string or a [`ClaimsRequest`](crate::claims_request::ClaimsRequest)
+    pub fn set_claims<T>(mut self, claims: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.claims = Some(claims.into());
+        self
+    }
+
+    /// Use [`CacheLookupPolicy::AccessToken`] to check the cache without any network calls
+    pub fn set_cache_lookup_policy(mut self, cache_lookup_policy: CacheLookupPolicy) -> Self {
+        self.cache_lookup_policy = Some(cache_lookup_policy);
+        self
+    }
+
+    /// Only sent to the token endpoint
+    pub fn set_token_query_parameters<T>(mut self, token_query_parameters: HashMap<T, T>) -> Self
+    where
+        T: Into<Cow<'a, str>> + Clone,
+    {
+        self.token_query_parameters = Some(cow_map(token_query_parameters));
+        self
+    }
+
+    pub fn set_extra_query_parameters<T>(mut self, extra_query_parameters: HashMap<T, T>) -> Self
+    where
+        T: Into<Cow<'a, str>> + Clone,
+    {
+        self.extra_query_parameters = Some(cow_map(extra_query_parameters));
+        self
+    }
+
+    pub fn set_state<T>(mut self, state: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// Treat the refresh token as expired this many seconds early.
+    /// **Has no effect** with the bundled msal-browser 2.33, which never reads it,
+    /// it is only passed through for later versions
+    pub fn set_refresh_token_expiration_offset_seconds(
+        mut self,
+        refresh_token_expiration_offset_seconds: u32,
+    ) -> Self {
+        self.refresh_token_expiration_offset_seconds =
+            Some(refresh_token_expiration_offset_seconds);
+        self
+    }
+
+    /// The http method of the request a PoP token is bound to
+    pub fn set_resource_request_method<T>(mut self, resource_request_method: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.resource_request_method = Some(resource_request_method.into());
+        self
+    }
+
+    /// The url of the request a PoP token is bound to
+    pub fn set_resource_request_uri<T>(mut self, resource_request_uri: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.resource_request_uri = Some(resource_request_uri.into());
+        self
+    }
+
+    pub fn account(&self) -> &AccountInfo {
+        &self.account
+    }
+
+    pub fn force_refresh(&self) -> Option<bool> {
+        self.force_refresh
+    }
+
+    pub fn redirect_uri(&self) -> Option<&str> {
+        self.redirect_uri.as_deref()
+    }
+
+    pub fn claims(&self) -> Option<&str> {
+        self.claims.as_deref()
+    }
+
+    pub fn cache_lookup_policy(&self) -> Option<&CacheLookupPolicy> {
+        self.cache_lookup_policy.as_ref()
+    }
+
+    pub fn token_query_parameters(&self) -> Option<&HashMap<Cow<'_, str>, Cow<'_, str>>> {
+        self.token_query_parameters.as_ref()
+    }
+
+    pub fn extra_query_parameters(&self) -> Option<&HashMap<Cow<'_, str>, Cow<'_, str>>> {
+        self.extra_query_parameters.as_ref()
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    pub fn refresh_token_expiration_offset_seconds(&self) -> Option<u32> {
+        self.refresh_token_expiration_offset_seconds
+    }
+
+    pub fn resource_request_method(&self) -> Option<&str> {
+        self.resource_request_method.as_deref()
+    }
+
+    pub fn resource_request_uri(&self) -> Option<&str> {
+        self.resource_request_uri.as_deref()
+    }
 }
 
-impl<'a> From<&'a SilentRequest<'a>> for msal::SilentRequest {
-    fn from(request: &'a SilentRequest) -> Self {
+impl<'a> From<msal::SilentRequest> for SilentRequest<'a> {
+    fn from(js: msal::SilentRequest) -> Self {
+        Self {
+            base_request: BaseAuthRequest::from_js(
+                Some(js.scopes()),
+                js.authority(),
+                js.correlation_id(),
+                js.authentication_scheme(),
+            ),
+            account: Cow::Owned(js.account().into()),
+            force_refresh: js.force_refresh(),
+            redirect_uri: js.redirect_uri().map(Cow::from),
+            claims: js.claims().map(Cow::from),
+            // Unknown values are dropped
+            cache_lookup_policy: js.cache_lookup_policy().and_then(|v| v.try_into().ok()),
+            token_query_parameters: js.token_query_parameters().map(|v| object_to_hash_map(&v)),
+            extra_query_parameters: js.extra_query_parameters().map(|v| object_to_hash_map(&v)),
+            state: js.state().map(Cow::from),
+            refresh_token_expiration_offset_seconds: js.refresh_token_expiration_offset_seconds(),
+            resource_request_method: js.resource_request_method().map(Cow::from),
+            resource_request_uri: js.resource_request_uri().map(Cow::from),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b SilentRequest<'a>> for msal::SilentRequest {
+    fn from(request: &'b SilentRequest<'a>) -> Self {
         let js = msal::SilentRequest::new(
             &JsArrayString::from(&request.base_request.scopes).into(),
-            request.account.into(),
+            request.account.as_ref().into(),
         );
 
         IterBaseAuthRequest {
@@ -519,6 +1156,7 @@ impl<'a> From<&'a SilentRequest<'a>> for msal::SilentRequest {
             destination: &js,
             authority: &|js, v| js.set_authority(v),
             correlation_id: &|js, v| js.set_correlation_id(v),
+            authentication_scheme: &|js, v| js.set_authentication_scheme(v.as_str()),
         }
         .iter_all();
 
@@ -528,13 +1166,189 @@ impl<'a> From<&'a SilentRequest<'a>> for msal::SilentRequest {
         if let Some(v) = &request.redirect_uri {
             js.set_redirect_uri(v)
         }
+        if let Some(v) = &request.claims {
+            js.set_claims(v)
+        }
+        if let Some(v) = &request.cache_lookup_policy {
+            js.set_cache_lookup_policy(v.as_u32())
+        }
+        if let Some(v) = &request.token_query_parameters {
+            js.set_token_query_parameters(JsHashMapStrStr::from(v).into())
+        }
+        if let Some(v) = &request.extra_query_parameters {
+            js.set_extra_query_parameters(JsHashMapStrStr::from(v).into())
+        }
+        if let Some(v) = &request.state {
+            js.set_state(v)
+        }
+        if let Some(v) = request.refresh_token_expiration_offset_seconds {
+            js.set_refresh_token_expiration_offset_seconds(v)
+        }
+        if let Some(v) = &request.resource_request_method {
+            js.set_resource_request_method(v)
+        }
+        if let Some(v) = &request.resource_request_uri {
+            js.set_resource_request_uri(v)
+        }
         js
     }
 }
 
-#[derive(Default, Clone)]
+/// Used for the hybrid SPA flow, where the server has already redeemed a confidential
+/// auth code and hands the `spa_code` to the browser
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthorizationCodeRequest<'a> {
+    base_request: BaseAuthRequest<'a>,
+    code: Option<Cow<'a, str>>,
+    native_account_id: Option<Cow<'a, str>>,
+    code_verifier: Option<Cow<'a, str>>,
+    claims: Option<Cow<'a, str>>,
+}
+
+impl<'a> SetBaseAuthRequest<'a> for AuthorizationCodeRequest<'a> {
+    fn base_request(&mut self) -> &mut BaseAuthRequest<'a> {
+        &mut self.base_request
+    }
+}
+
+impl<'a> GetBaseAuthRequest for AuthorizationCodeRequest<'a> {
+    fn get_base_request(&self) -> &BaseAuthRequest<'_> {
+        &self.base_request
+    }
+}
+
+impl<'a> AuthorizationCodeRequest<'a> {
+    pub fn new<T>(scopes: &[T]) -> Self
+    where
+        T: Clone + Into<Cow<'a, str>>,
+    {
+        Self {
+            base_request: BaseAuthRequest::new(scopes),
+            code: None,
+            native_account_id: None,
+            code_verifier: None,
+            claims: None,
+        }
+    }
+
+    /// See [`AuthorizationUrlRequest::into_owned`]
+    pub fn into_owned(self) -> AuthorizationCodeRequest<'static> {
+        AuthorizationCodeRequest {
+            base_request: self.base_request.into_owned(),
+            code: self.code.map(owned),
+            native_account_id: self.native_account_id.map(owned),
+            code_verifier: self.code_verifier.map(owned),
+            claims: self.claims.map(owned),
+        }
+    }
+
+    pub fn set_code<T>(mut self, code: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn set_native_account_id<T>(mut self, native_account_id: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.native_account_id = Some(native_account_id.into());
+        self
+    }
+
+    pub fn set_code_verifier<T>(mut self, code_verifier: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.code_verifier = Some(code_verifier.into());
+        self
+    }
+
+    /// Sets the code verifier that matches the challenge sent on the [`AuthorizationUrlRequest`]
+    pub fn set_pkce(mut self, pkce: &'a PkceCodes) -> Self {
+        self.code_verifier = Some(pkce.verifier().into());
+        self
+    }
+
+    /// Accepts a json string or a [`ClaimsRequest`](crate::claims_request::ClaimsRequest)
+    pub fn set_claims<T>(mut self, claims: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.claims = Some(claims.into());
+        self
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn native_account_id(&self) -> Option<&str> {
+        self.native_account_id.as_deref()
+    }
+
+    pub fn code_verifier(&self) -> Option<&str> {
+        self.code_verifier.as_deref()
+    }
+
+    pub fn claims(&self) -> Option<&str> {
+        self.claims.as_deref()
+    }
+}
+
+impl<'a> From<msal::AuthorizationCodeRequest> for AuthorizationCodeRequest<'a> {
+    fn from(js: msal::AuthorizationCodeRequest) -> Self {
+        Self {
+            base_request: BaseAuthRequest::from_js(
+                js.scopes(),
+                js.authority(),
+                js.correlation_id(),
+                js.authentication_scheme(),
+            ),
+            code: js.code().map(Cow::from),
+            native_account_id: js.native_account_id().map(Cow::from),
+            code_verifier: js.code_verifier().map(Cow::from),
+            claims: js.claims().map(Cow::from),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b AuthorizationCodeRequest<'a>> for msal::AuthorizationCodeRequest {
+    fn from(request: &'b AuthorizationCodeRequest<'a>) -> Self {
+        let js = msal::AuthorizationCodeRequest::new(
+            &JsArrayString::from(&request.base_request.scopes).into(),
+        );
+
+        IterBaseAuthRequest {
+            base_auth_request: &request.base_request,
+            destination: &js,
+            authority: &|js, v| js.set_authority(v),
+            correlation_id: &|js, v| js.set_correlation_id(v),
+            authentication_scheme: &|js, v| js.set_authentication_scheme(v.as_str()),
+        }
+        .iter_all();
+
+        if let Some(v) = &request.code {
+            js.set_code(v)
+        }
+        if let Some(v) = &request.native_account_id {
+            js.set_native_account_id(v)
+        }
+        if let Some(v) = &request.code_verifier {
+            js.set_code_verifier(v)
+        }
+        if let Some(v) = &request.claims {
+            js.set_claims(v)
+        }
+        js
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct EndSessionRequest<'a> {
-    account: Option<&'a AccountInfo>,
+    account: Option<Cow<'a, AccountInfo>>,
     post_logout_redirect_uri: Option<Cow<'a, str>>,
     authority: Option<Cow<'a, str>>,
     correlation_id: Option<Cow<'a, str>>,
@@ -545,8 +1359,21 @@ impl<'a> EndSessionRequest<'a> {
         Self::default()
     }
 
-    pub fn set_account(mut self, account: &'a AccountInfo) -> Self {
-        self.account = Some(account);
+    /// See [`AuthorizationUrlRequest::into_owned`]
+    pub fn into_owned(self) -> EndSessionRequest<'static> {
+        EndSessionRequest {
+            account: self.account.map(|v| Cow::Owned(v.into_owned())),
+            post_logout_redirect_uri: self.post_logout_redirect_uri.map(owned),
+            authority: self.authority.map(owned),
+            correlation_id: self.correlation_id.map(owned),
+        }
+    }
+
+    pub fn set_account<T>(mut self, account: T) -> Self
+    where
+        T: Into<Cow<'a, AccountInfo>>,
+    {
+        self.account = Some(account.into());
         self
     }
 
@@ -573,13 +1400,40 @@ impl<'a> EndSessionRequest<'a> {
         self.correlation_id = Some(correlation_id.into());
         self
     }
+
+    pub fn account(&self) -> Option<&AccountInfo> {
+        self.account.as_deref()
+    }
+
+    pub fn post_logout_redirect_uri(&self) -> Option<&str> {
+        self.post_logout_redirect_uri.as_deref()
+    }
+
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    pub fn correlation_id(&self) -> Option<&str> {
+        self.correlation_id.as_deref()
+    }
+}
+
+impl<'a> From<msal::EndSessionRequest> for EndSessionRequest<'a> {
+    fn from(js: msal::EndSessionRequest) -> Self {
+        Self {
+            account: js.account().map(|v| Cow::Owned(v.into())),
+            post_logout_redirect_uri: js.post_logout_redirect_uri().map(Cow::from),
+            authority: js.authority().map(Cow::from),
+            correlation_id: js.correlation_id().map(Cow::from),
+        }
+    }
 }
 
 impl<'a> From<EndSessionRequest<'a>> for msal::EndSessionRequest {
     fn from(request: EndSessionRequest) -> Self {
         let js = msal::EndSessionRequest::new();
-        if let Some(v) = request.account {
-            js.set_account(v.into())
+        if let Some(v) = &request.account {
+            js.set_account(v.as_ref().into())
         }
         if let Some(v) = &request.post_logout_redirect_uri {
             js.set_post_logout_redirect_uri(&v)
@@ -600,39 +1454,169 @@ mod test_request {
 
     use super::*;
     use crate::tests::*;
+    use js_sys::Object;
+    use wasm_bindgen::prelude::*;
     use wasm_bindgen_test::*;
     use web_sys::console;
 
     const FORCE_REFRESH: bool = true;
     const REDIRECT_URI: &str = "redirect_uri";
+    const CODE: &str = "code";
+    const NATIVE_ACCOUNT_ID: &str = "native_account_id";
+    const CODE_VERIFIER: &str = "code_verifier";
+    const EXTRA_SCOPE: &str = "extra_scope";
+    const CODE_CHALLENGE: &str = "code_challenge";
+    const CODE_CHALLENGE_METHOD: &str = "S256";
+    const STATE: &str = "state";
+    const LOGIN_HINT: &str = "login_hint";
+    const DOMAIN_HINT: &str = "domain_hint";
+    const EXTRA_QUERY_KEY: &str = "extra_query_key";
+    const EXTRA_QUERY_VALUE: &str = "extra_query_value";
+    const CLAIMS: &str = "claims";
+    const NONCE: &str = "nonce";
+
+    #[wasm_bindgen(module = "/js/msal-object-examples.js")]
+    extern "C" {
+        static authorizationUrlRequest: Object;
+    }
+
+    fn set_all<'a, R>(req: R) -> R
+    where
+        R: SetAuthorizationUrlRequest<'a> + SetBaseAuthRequest<'a>,
+    {
+        let mut extra_query_parameters = HashMap::new();
+        extra_query_parameters.insert(EXTRA_QUERY_KEY, EXTRA_QUERY_VALUE);
+        req.set_authority(AUTHORITY)
+            .set_correlation_id(CORRELATION_ID)
+            .set_redirect_uri(REDIRECT_URI)
+            .set_extra_scopes_to_consent(&[EXTRA_SCOPE])
+            .set_response_mode(ResponseMode::Fragment)
+            .set_code_challenge(CODE_CHALLENGE)
+            .set_code_challenge_method(CODE_CHALLENGE_METHOD)
+            .set_state(STATE)
+            .set_prompt(Prompt::SelectAccount)
+            .set_login_hint(LOGIN_HINT)
+            .set_domain_hint(DOMAIN_HINT)
+            .set_extra_query_parameters(extra_query_parameters)
+            .set_claims(CLAIMS)
+            .set_nonce(NONCE)
+    }
 
     #[wasm_bindgen_test]
     fn mirror_auth_url_request() {
-        let req = AuthorizationUrlRequest::new(&[SCOPE][..]);
+        let req = set_all(AuthorizationUrlRequest::new(&[SCOPE][..]));
         let js_req: msal::AuthorizationUrlRequest = (&req).into();
 
         console::log_1(&"AuthorizationUrlRequest:".into());
         console::log_1(&js_req);
-        // TODO: Write tests
+
+        assert_eq!(
+            req,
+            js_req
+                .clone()
+                .unchecked_into::<msal::AuthorizationUrlRequest>()
+                .into()
+        );
         js_cast_checker::<msal::AuthorizationUrlRequest>(js_req.into());
     }
 
     #[wasm_bindgen_test]
     #[cfg(feature = "redirect")]
     fn mirror_redirect_request() {
-        let req = RedirectRequest::new(&[SCOPE][..]);
+        let req = set_all(RedirectRequest::new(&[SCOPE][..])).set_redirect_start_page(REDIRECT_URI);
         let js_req: msal::RedirectRequest = (&req).into();
 
         console::log_1(&"RedirectRequest:".into());
         console::log_1(&js_req);
-        // TODO: Write tests
+
+        assert_eq!(
+            req,
+            js_req
+                .clone()
+                .unchecked_into::<msal::RedirectRequest>()
+                .into()
+        );
         js_cast_checker::<msal::RedirectRequest>(js_req.into());
     }
 
+    #[wasm_bindgen_test]
+    fn prompt_and_response_mode_strings() {
+        let prompts = [
+            Prompt::Login,
+            Prompt::None,
+            Prompt::Consent,
+            Prompt::SelectAccount,
+            Prompt::Create,
+            Prompt::NoSession,
+        ];
+        for prompt in prompts {
+            assert_eq!(prompt.to_string().parse(), Ok(prompt));
+        }
+        let response_modes = [
+            ResponseMode::Query,
+            ResponseMode::Fragment,
+            ResponseMode::FormPost,
+        ];
+        for response_mode in response_modes {
+            assert_eq!(response_mode.to_string().parse(), Ok(response_mode));
+        }
+        assert!("select-account".parse::<Prompt>().is_err());
+        assert!("post".parse::<ResponseMode>().is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn check_response_mode() {
+        let req = AuthorizationUrlRequest::new(&[SCOPE][..]);
+        assert_eq!(req.check_response_mode(), Ok(()));
+
+        let req = req.set_response_mode(ResponseMode::Fragment);
+        assert_eq!(req.check_response_mode(), Ok(()));
+
+        let req = req.set_response_mode(ResponseMode::FormPost);
+        assert_eq!(
+            req.check_response_mode(),
+            Err(RequestError::UnsupportedResponseMode(
+                ResponseMode::FormPost
+            ))
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_js_auth_url_request() {
+        let req: AuthorizationUrlRequest = authorizationUrlRequest
+            .clone()
+            .unchecked_into::<msal::AuthorizationUrlRequest>()
+            .into();
+
+        assert_eq!(req.scopes(), &["openid", "profile"]);
+        assert_eq!(req.authority(), Some(AUTHORITY));
+        assert_eq!(req.prompt(), Some(&Prompt::SelectAccount));
+        assert_eq!(req.response_mode(), Some(&ResponseMode::Fragment));
+        assert_eq!(req.login_hint(), Some(LOGIN_HINT));
+        assert_eq!(
+            req.extra_query_parameters()
+                .and_then(|v| v.get(EXTRA_QUERY_KEY))
+                .map(|v| v.as_ref()),
+            Some(EXTRA_QUERY_VALUE)
+        );
+        assert_eq!(req.nonce(), None);
+    }
+
     #[wasm_bindgen_test]
     fn mirror_silent_request() {
         let account = account();
+        let mut extra_query_parameters = HashMap::new();
+        extra_query_parameters.insert(EXTRA_QUERY_KEY, EXTRA_QUERY_VALUE);
         let req = SilentRequest::new(&[SCOPE][..], &account)
+            .set_claims(CLAIMS)
+            .set_cache_lookup_policy(CacheLookupPolicy::AccessToken)
+            .set_token_query_parameters(extra_query_parameters.clone())
+            .set_extra_query_parameters(extra_query_parameters)
+            .set_state(STATE)
+            .set_refresh_token_expiration_offset_seconds(300)
+            .set_authentication_scheme(AuthenticationScheme::Pop)
+            .set_resource_request_method("GET")
+            .set_resource_request_uri(REDIRECT_URI)
             .set_force_refresh(FORCE_REFRESH)
             .set_redirect_uri(REDIRECT_URI)
             .set_correlation_id(CORRELATION_ID)
@@ -643,6 +1627,14 @@ mod test_request {
         console::log_1(&"SilentRequest:".into());
         console::log_1(&js_req);
 
+        assert_eq!(
+            req,
+            js_req
+                .clone()
+                .unchecked_into::<msal::SilentRequest>()
+                .into()
+        );
+
         assert_eq!(
             req.base_request.scopes,
             JsArrayString::from(js_req.scopes()).0
@@ -663,11 +1655,118 @@ mod test_request {
         assert_eq!(req.account.tenant_id, js_req.account().tenant_id());
         assert_eq!(req.account.username, js_req.account().username());
         assert_eq!(req.force_refresh, js_req.force_refresh());
+        assert_eq!(req.claims.as_deref().map(String::from), js_req.claims());
+        assert_eq!(js_req.cache_lookup_policy(), Some(1));
+        assert_eq!(js_req.authentication_scheme().as_deref(), Some("pop"));
+        assert_eq!(js_req.resource_request_method().as_deref(), Some("GET"));
+        assert_eq!(js_req.resource_request_uri().as_deref(), Some(REDIRECT_URI));
+        assert_eq!(
+            req.token_query_parameters,
+            js_req
+                .token_query_parameters()
+                .map(|v| object_to_hash_map(&v))
+        );
+        assert_eq!(
+            req.extra_query_parameters,
+            js_req
+                .extra_query_parameters()
+                .map(|v| object_to_hash_map(&v))
+        );
+        assert_eq!(req.state.as_deref().map(String::from), js_req.state());
+        assert_eq!(
+            req.refresh_token_expiration_offset_seconds,
+            js_req.refresh_token_expiration_offset_seconds()
+        );
         assert_eq!(req.redirect_uri.map(Cow::into_owned), js_req.redirect_uri());
 
         js_cast_checker::<msal::SilentRequest>(js_req.into());
     }
 
+    #[wasm_bindgen_test]
+    fn cache_lookup_policy_numbers() {
+        for i in 0..6 {
+            let policy = CacheLookupPolicy::try_from(i).unwrap();
+            assert_eq!(policy.as_u32(), i);
+        }
+        assert!(CacheLookupPolicy::try_from(6).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn mirror_authorization_code_request() {
+        let req = AuthorizationCodeRequest::new(&[SCOPE][..])
+            .set_code(CODE)
+            .set_native_account_id(NATIVE_ACCOUNT_ID)
+            .set_code_verifier(CODE_VERIFIER)
+            .set_claims(CLAIMS)
+            .set_correlation_id(CORRELATION_ID)
+            .set_authority(AUTHORITY);
+
+        let js_req: msal::AuthorizationCodeRequest = (&req).into();
+
+        console::log_1(&"AuthorizationCodeRequest:".into());
+        console::log_1(&js_req);
+
+        assert_eq!(
+            req,
+            js_req
+                .clone()
+                .unchecked_into::<msal::AuthorizationCodeRequest>()
+                .into()
+        );
+
+        assert_eq!(
+            req.base_request.scopes,
+            JsArrayString::from(js_req.scopes().unwrap()).0
+        );
+        assert_eq!(
+            req.base_request.correlation_id.as_deref().map(String::from),
+            js_req.correlation_id()
+        );
+        assert_eq!(
+            req.base_request.authority.as_deref().map(String::from),
+            js_req.authority()
+        );
+        assert_eq!(req.code.map(Cow::into_owned), js_req.code());
+        assert_eq!(
+            req.native_account_id.map(Cow::into_owned),
+            js_req.native_account_id()
+        );
+        assert_eq!(
+            req.code_verifier.map(Cow::into_owned),
+            js_req.code_verifier()
+        );
+
+        js_cast_checker::<msal::AuthorizationCodeRequest>(js_req.into());
+    }
+
+    // Checked at compile time: owned inputs give a `'static` request
+    fn owned_silent_request() -> SilentRequest<'static> {
+        let scopes = vec![SCOPE.to_string()];
+        SilentRequest::new(&scopes, account()).set_authority(AUTHORITY.to_string())
+    }
+
+    #[wasm_bindgen_test]
+    fn owned_requests() {
+        let req = owned_silent_request();
+        let js_req: msal::SilentRequest = (&req).into();
+        assert_eq!(js_req.authority().as_deref(), Some(AUTHORITY));
+        assert_eq!(req.account.username, js_req.account().username());
+
+        let authority = String::from(AUTHORITY);
+        let req = AuthorizationUrlRequest::new(&[SCOPE][..])
+            .set_authority(authority.as_str())
+            .into_owned();
+        drop(authority);
+        let js_req: msal::AuthorizationUrlRequest = (&req).into();
+        assert_eq!(js_req.authority().as_deref(), Some(AUTHORITY));
+
+        let account = account();
+        let req = EndSessionRequest::new().set_account(&account).into_owned();
+        drop(account);
+        let js_req: msal::EndSessionRequest = req.into();
+        assert_eq!(js_req.account().unwrap().username(), USERNAME);
+    }
+
     #[wasm_bindgen_test]
     fn mirror_end_session_request() {
         let account = account();
@@ -682,6 +1781,14 @@ mod test_request {
         console::log_1(&"EndSessionRequest:".into());
         console::log_1(&js_req);
 
+        assert_eq!(
+            req,
+            js_req
+                .clone()
+                .unchecked_into::<msal::EndSessionRequest>()
+                .into()
+        );
+
         assert_eq!(
             req.correlation_id.map(Cow::into_owned),
             js_req.correlation_id()
@@ -0,0 +1,137 @@
+//! Conversions to `SystemTime`, since `SystemTime::now` panics on `wasm32-unknown-unknown`
+//! the current time comes from js `Date` in the browser.
+
+use js_sys::{Date, Function, Promise};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// The longest delay `setTimeout` accepts, ~24.8 days
+const MAX_TIMEOUT_MILLIS: u128 = i32::MAX as u128;
+
+/// The latest time a js `Date` can hold, later times saturate to this
+const MAX_DATE: Duration = Duration::from_millis(8_640_000_000_000_000);
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &Function, timeout: i32) -> JsValue;
+}
+
+/// The current time, safe to call in the browser
+pub fn now() -> SystemTime {
+    #[cfg(target_arch = "wasm32")]
+    {
+        from_millis(Date::now())
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SystemTime::now()
+    }
+}
+
+/// From a JWT NumericDate, seconds since the unix epoch
+pub fn from_seconds(seconds: f64) -> SystemTime {
+    from_millis(seconds * 1000.0)
+}
+
+pub fn to_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0.0, |v| v.as_secs_f64())
+}
+
+// Negative and NaN are the epoch
+fn from_millis(millis: f64) -> SystemTime {
+    Duration::try_from_secs_f64(millis / 1000.0)
+        .ok()
+        .map(|v| v.min(MAX_DATE))
+        .and_then(|v| UNIX_EPOCH.checked_add(v))
+        .unwrap_or(if millis > 0.0 {
+            UNIX_EPOCH + MAX_DATE
+        } else {
+            UNIX_EPOCH
+        })
+}
+
+/// Accepts a js `Date` or anything the `Date` constructor can parse
+pub(crate) fn from_js_date(date: &JsValue) -> SystemTime {
+    from_millis(Date::new(date).get_time())
+}
+
+/// Time left until `expires`, zero once passed
+pub(crate) fn expires_in(expires: SystemTime) -> Duration {
+    expires.duration_since(now()).unwrap_or_default()
+}
+
+/// Lenient: allows for the local clock being up to `skew` ahead of the server.
+/// A skew too large to add is never expired
+pub(crate) fn is_expired(expires: SystemTime, skew: Duration) -> bool {
+    expires.checked_add(skew).is_some_and(|v| v <= now())
+}
+
+/// Strict: true once within `offset` of `expires`, the same check msal makes
+/// with `tokenRenewalOffsetSeconds`. An offset too large to add always refreshes
+pub(crate) fn should_refresh(expires: SystemTime, offset: Duration) -> bool {
+    now().checked_add(offset).is_none_or(|v| expires <= v)
+}
+
+/// Resolves after `duration` using `setTimeout`, longer durations are capped
+pub(crate) async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(MAX_TIMEOUT_MILLIS) as i32;
+    let promise = Promise::new(&mut |resolve, _| {
+        set_timeout(&resolve, millis);
+    });
+    // Never rejects
+    let _ = JsFuture::from(promise).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_round_trip() {
+        let time = from_seconds(1536361411.0);
+        assert_eq!(time, UNIX_EPOCH + Duration::from_secs(1536361411));
+        assert_eq!(to_seconds(time), 1536361411.0);
+        assert_eq!(from_seconds(-1.0), UNIX_EPOCH);
+        assert_eq!(from_seconds(f64::NAN), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn large_seconds_saturate() {
+        let latest = UNIX_EPOCH + MAX_DATE;
+        assert_eq!(from_seconds(1e20), latest);
+        assert_eq!(from_seconds(f64::MAX), latest);
+        assert_eq!(from_seconds(f64::INFINITY), latest);
+        assert_eq!(from_seconds(f64::NEG_INFINITY), UNIX_EPOCH);
+        assert!(!is_expired(from_seconds(1e20), Duration::from_secs(300)));
+        assert!(!should_refresh(
+            from_seconds(f64::MAX),
+            Duration::from_secs(300)
+        ));
+    }
+
+    #[test]
+    fn large_durations_dont_overflow() {
+        let past = now() - Duration::from_secs(60);
+        assert!(!is_expired(past, Duration::MAX));
+        assert!(!is_expired(from_seconds(f64::MAX), Duration::MAX));
+        assert!(should_refresh(from_seconds(f64::MAX), Duration::MAX));
+    }
+
+    #[test]
+    fn expiry() {
+        let minute = Duration::from_secs(60);
+        let past = now() - minute;
+        let future = now() + 10 * minute;
+
+        assert!(is_expired(past, Duration::ZERO));
+        assert!(!is_expired(past, 2 * minute));
+        assert!(!is_expired(future, Duration::ZERO));
+        assert_eq!(expires_in(past), Duration::ZERO);
+        assert!(expires_in(future) > 9 * minute);
+        assert!(should_refresh(future, 11 * minute));
+        assert!(!should_refresh(future, 5 * minute));
+    }
+}
@@ -1,6 +1,14 @@
-use js_sys::{Array, Object};
+use crate::{
+    jwt::{JsonObject, Jwt, JwtError},
+    time,
+};
+use js_sys::{Array, Object, Reflect};
 use paste::paste;
-use std::convert::{TryFrom, TryInto};
+use std::{
+    collections::{btree_map, BTreeMap},
+    convert::TryFrom,
+    time::{Duration, SystemTime},
+};
 use wasm_bindgen::{JsCast, JsValue};
 
 // https://docs.microsoft.com/en-us/azure/active-directory/develop/access-tokens
@@ -10,20 +18,69 @@ use wasm_bindgen::{JsCast, JsValue};
 // https://tools.ietf.org/html/rfc7515
 // https://www.iana.org/assignments/jwt/jwt.xhtml#claims
 
+// The claim name is the ident unless one is given
 macro_rules! generate_claims {
-    ( $( ($i:ident, $t:ty) ),+ ) => {
+    ( $( ($i:ident, $t:ty $(, $name:literal )? ) ),+ ) => {
 
         /// Covers all the claims as per the  IETF spec. If the claim doesn't match any of the standard ones
         /// it will return `Custom::(claim_name, claim_value)`
         /// Adds the azure specific ones too
-        #[derive(Clone, PartialEq)]
+        #[derive(Clone, Debug, PartialEq)]
         #[allow(non_camel_case_types)]
         pub enum TokenClaim {
             typ, // Always JWT
             $(
                 $i ($t),
             )+
-            custom(String, JsValue), // Custom to cover all else
+            custom(String, serde_json::Value), // Custom to cover all else
+        }
+
+        impl TokenClaim {
+            /// The claim name as it appears in the token
+            pub fn name(&self) -> &str {
+                match self {
+                    Self::typ => "typ",
+                    $(
+                        Self::$i(_) => generate_claims!(@name $i $(, $name )?),
+                    )+
+                    Self::custom(name, _) => name,
+                }
+            }
+
+            /// The claim value as a js value, only available in the browser.
+            /// See [`TokenClaim::to_json`] for the native equivalent
+            pub fn value(&self) -> JsValue {
+                match self {
+                    Self::typ => "JWT".into(),
+                    $(
+                        Self::$i(v) => v.to_js(),
+                    )+
+                    Self::custom(_, v) => json_to_js(v).unwrap_or(JsValue::UNDEFINED),
+                }
+            }
+
+            /// The claim value as it was in the token
+            pub fn to_json(&self) -> serde_json::Value {
+                match self {
+                    Self::typ => "JWT".into(),
+                    $(
+                        Self::$i(v) => v.to_json(),
+                    )+
+                    Self::custom(_, v) => v.clone(),
+                }
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl TokenClaims {
+            $(
+                pub fn $i(&self) -> Option<&$t> {
+                    match self.get(generate_claims!(@name $i $(, $name )?)) {
+                        Some(TokenClaim::$i(v)) => Some(v),
+                        _ => None,
+                    }
+                }
+            )+
         }
 
         impl TryFrom<JsValue> for TokenClaim {
@@ -33,45 +90,58 @@ macro_rules! generate_claims {
                 let kv = js_value.unchecked_into::<Array>();
                 let value = kv.get(1);
                 let key: String = kv.get(0).as_string().unwrap();
-                let make_string = |f: &dyn Fn(String) -> Self, key, value: JsValue| match value.as_string()
-                {
-                    Some(value) => Ok(f(value)),
-                    None => Err((key, value)),
-                };
-                let make_f64 = |f: &dyn Fn(f64) -> Self, key, value: JsValue| match value.as_f64() {
-                    Some(value) => Ok(f(value.to_owned())),
-                    None => Err((key, value)),
-                };
-                let make_bool = |f: &dyn Fn(bool) -> Self, key, value: JsValue| match value.as_bool() {
-                    Some(value) => Ok(f(value)),
-                    None => Err((key, value)),
-                };
-                let make_array = |f: &dyn Fn(Array) -> Self, key, value: JsValue| match value.dyn_into() {
-                    Ok(value) => Ok(f(value)),
-                    Err(value) => Err((key, value)),
-                };
-                let make_object = |f: &dyn Fn(Object) -> Self, key, value: JsValue| {
-                    if value.is_object() {
-                        Ok(f(value.unchecked_into()))
-                    } else {
-                        Err((key, value))
-                    }
-                };
+                let make_string = make::<String>;
+                let make_f64 = make::<f64>;
+                let make_systemtime = make::<SystemTime>;
+                let make_bool = make::<bool>;
+                let make_strings = make::<Strings>;
+                let make_address = make_structured::<Address>;
+                let make_confirmation = make_structured::<Confirmation>;
+                let make_actor = make_structured::<Actor>;
+                let make_object = make::<Object>;
+                let make_jsonobject = make::<JsonObject>;
 
                 // Returned keys are always strings
                 paste! {
                     match key.as_str() {
                         "typ" => Ok(Self::typ), // Always in JWT
                             $(
-                                stringify!($i) => [< make_ $t:lower >] (&Self::$i, key, value),
+                                generate_claims!(@name $i $(, $name )?) => [< make_ $t:lower >] (&Self::$i, key, value),
                             ) +
                             // Catch anything not matched
-                            _ => Ok(Self::custom(key, value)),
+                            _ => Ok(Self::custom(key, js_to_json(&value).unwrap_or_default())),
                             }
                 }
             }
         }
-    }
+
+        impl TokenClaim {
+            fn from_json(key: &str, value: &serde_json::Value) -> Option<Self> {
+                let json_string = from_json::<String>;
+                let json_f64 = from_json::<f64>;
+                let json_systemtime = from_json::<SystemTime>;
+                let json_bool = from_json::<bool>;
+                let json_strings = from_json::<Strings>;
+                let json_address = from_json_structured::<Address>;
+                let json_confirmation = from_json_structured::<Confirmation>;
+                let json_actor = from_json_structured::<Actor>;
+                let json_object = from_json::<Object>;
+                let json_jsonobject = from_json::<JsonObject>;
+
+                paste! {
+                    match key {
+                        "typ" => Some(Self::typ),
+                        $(
+                            generate_claims!(@name $i $(, $name )?) => [< json_ $t:lower >] (&Self::$i, key, value),
+                        )+
+                        _ => Some(Self::custom(key.to_owned(), value.clone())),
+                    }
+                }
+            }
+        }
+    };
+    (@name $i:ident) => { stringify!($i) };
+    (@name $i:ident, $name:literal) => { $name };
 }
 
 generate_claims! {
@@ -82,9 +152,9 @@ generate_claims! {
      (iss, String),
      (sub, String),
      (aud, String),
-     (exp, f64),
-     (nbf, f64),
-     (iat, f64),
+     (exp, SystemTime),
+     (nbf, SystemTime),
+     (iat, SystemTime),
      (jti, String),
      (name, String),
      (given_name, String),
@@ -103,11 +173,11 @@ generate_claims! {
      (locale, String),
      (phone_number, String),
      (phone_number_verified, bool),
-     (address, Object),
-     (updated_at, f64),
-     (cnf, Object),
+     (address, Address),
+     (updated_at, SystemTime),
+     (cnf, Confirmation),
      (sip_from_tag, String),
-     (sip_date, f64),
+     (sip_date, SystemTime),
      (sip_callid, String),
      (sip_cseq_num, String),
      (sip_via_branch, String),
@@ -115,7 +185,7 @@ generate_claims! {
      (dest, Object),
      (mky, Object),
      (events, Object),
-     (toe, f64),
+     (toe, SystemTime),
      (txn, String),
      (rph, Object),
      (sid, String),
@@ -123,10 +193,10 @@ generate_claims! {
      (vtm, String),
      (attest, String),
      (origid, String),
-     (act, Object),
+     (act, Actor),
      (scope, String),
      (client_id, String),
-     (may_act, Object),
+     (may_act, Actor),
      (jcard, Object),
      (at_use_nbr, f64), // Technically u32?
      (div, Object),
@@ -143,31 +213,772 @@ generate_claims! {
      (scp, String),
      (uti, String),
      (appid, String),
-     (roles, Array),
-     (wids, Array),
-     // TODO: groups:src1 // _claim_sources?
-     (groups, Array),
-     (hasgroups, bool)
+     (roles, Strings),
+     (wids, Strings),
+     (groups, Strings),
+     (hasgroups, bool),
+     // Groups overage, e.g. `"groups": "src1"`
+     (claim_names, JsonObject, "_claim_names"),
+     (claim_sources, JsonObject, "_claim_sources"),
+     // Azure AD B2C
+     (tfp, String),
+     (acr, String),
+     (emails, Strings),
+     (newUser, bool)
+}
+
+/// Conversion of a claim from its js or json value, implement to use your own types with
+/// [`custom_claims!`](crate::custom_claims) or [`TokenClaims::get_as`].
+/// The json conversions default to going through the js ones so only work in the browser,
+/// override them to use your type natively
+pub trait ClaimValue: Sized {
+    fn from_js(value: &JsValue) -> Option<Self>;
+
+    fn to_js(&self) -> JsValue;
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Self::from_js(&json_to_js(value)?)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        js_to_json(&self.to_js()).unwrap_or_default()
+    }
+}
+
+impl ClaimValue for String {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value.as_string()
+    }
+
+    fn to_js(&self) -> JsValue {
+        self.into()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_str().map(str::to_owned)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        self.as_str().into()
+    }
+}
+
+impl ClaimValue for f64 {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value.as_f64()
+    }
+
+    fn to_js(&self) -> JsValue {
+        (*self).into()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_f64()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        number_to_json(*self)
+    }
+}
+
+impl ClaimValue for bool {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn to_js(&self) -> JsValue {
+        (*self).into()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_bool()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        (*self).into()
+    }
+}
+
+/// A NumericDate, seconds since the unix epoch
+impl ClaimValue for SystemTime {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value.as_f64().map(time::from_seconds)
+    }
+
+    fn to_js(&self) -> JsValue {
+        time::to_seconds(*self).into()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_f64().map(time::from_seconds)
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        number_to_json(time::to_seconds(*self))
+    }
+}
+
+/// Only if every item is a string
+impl ClaimValue for Vec<String> {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value
+            .dyn_ref::<Array>()
+            .and_then(|v| v.iter().map(|v| v.as_string()).collect())
+    }
+
+    fn to_js(&self) -> JsValue {
+        self.iter().map(JsValue::from).collect::<Array>().into()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value
+            .as_array()?
+            .iter()
+            .map(|v| v.as_str().map(str::to_owned))
+            .collect()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        self.as_slice().into()
+    }
+}
+
+impl ClaimValue for Array {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        value.dyn_ref::<Array>().cloned()
+    }
+
+    fn to_js(&self) -> JsValue {
+        self.into()
+    }
+}
+
+impl ClaimValue for Object {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        if value.is_object() {
+            Some(value.clone().unchecked_into())
+        } else {
+            None
+        }
+    }
+
+    fn to_js(&self) -> JsValue {
+        self.into()
+    }
+}
+
+/// Kept as json so it can be read natively
+impl ClaimValue for JsonObject {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        match js_to_json(value)? {
+            serde_json::Value::Object(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn to_js(&self) -> JsValue {
+        // A json map always serializes and parses
+        js_sys::JSON::parse(&serde_json::to_string(self).unwrap()).unwrap()
+    }
+
+    fn from_json(value: &serde_json::Value) -> Option<Self> {
+        value.as_object().cloned()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        self.clone().into()
+    }
 }
 
-#[derive(Clone)]
-pub struct TokenClaims(pub Vec<TokenClaim>);
+impl ClaimValue for JsValue {
+    fn from_js(value: &JsValue) -> Option<Self> {
+        Some(value.clone())
+    }
+
+    fn to_js(&self) -> JsValue {
+        self.clone()
+    }
+}
+
+macro_rules! structured_claim_value {
+    ( $( $t:ty ),+ ) => {
+        $(
+            impl ClaimValue for $t {
+                fn from_js(value: &JsValue) -> Option<Self> {
+                    Self::try_from(Object::from_js(value)?).ok()
+                }
+
+                fn to_js(&self) -> JsValue {
+                    self.raw().into()
+                }
+            }
+        )+
+    };
+}
+
+structured_claim_value!(Address, Confirmation, Actor);
+
+// Whole numbers as integers, as they were in the token
+fn number_to_json(value: f64) -> serde_json::Value {
+    if value.fract() == 0.0 && (0.0..u64::MAX as f64).contains(&value) {
+        (value as u64).into()
+    } else {
+        value.into()
+    }
+}
+
+// Claims held as js values can only be made in the browser
+fn json_to_js(value: &serde_json::Value) -> Option<JsValue> {
+    if cfg!(target_arch = "wasm32") {
+        js_sys::JSON::parse(&value.to_string()).ok()
+    } else {
+        None
+    }
+}
+
+fn js_to_json(value: &JsValue) -> Option<serde_json::Value> {
+    let json = js_sys::JSON::stringify(value).ok()?.as_string()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Declares a struct of your own claims, e.g. directory extensions or custom B2C attributes,
+/// that can be made from [`TokenClaims`]. The field name is the claim name unless one is given.
+/// Every field is an `Option` since any claim may be missing or of the wrong type.
+/// Made through [`ClaimValue::from_json`] so works natively for the crate's own value types.
+/// ```ignore
+/// msal_browser::custom_claims! {
+///     pub struct MyClaims {
+///         (ctry, String),
+///         (department, String, "extension_b7d8e2c4a1f94a0e8f1d3c6b5a4e2d1f_department"),
+///         (clearance, f64),
+///     }
+/// }
+/// let claims = MyClaims::from(auth_result.id_token_claims());
+/// ```
+#[macro_export]
+macro_rules! custom_claims {
+    (
+        $( #[$meta:meta] )*
+        $vis:vis struct $name:ident {
+            $( ($field:ident, $t:ty $(, $claim:literal )? ) ),* $(,)?
+        }
+    ) => {
+        $( #[$meta] )*
+        #[derive(Clone, Debug, PartialEq)]
+        $vis struct $name {
+            $( pub $field: Option<$t>, )*
+        }
+
+        impl From<&$crate::TokenClaims> for $name {
+            fn from(claims: &$crate::TokenClaims) -> Self {
+                Self {
+                    $(
+                        $field: claims.get_as::<$t>(
+                            $crate::custom_claims!(@name $field $(, $claim )?)
+                        ),
+                    )*
+                }
+            }
+        }
+    };
+    (@name $field:ident) => { stringify!($field) };
+    (@name $field:ident, $claim:literal) => { $claim };
+}
+
+const EXTENSION_PREFIX: &str = "extension_";
+/// Claims removed by [`TokenClaims::redacted`]
+pub const PII_CLAIMS: [&str; 10] = [
+    "email",
+    "emails",
+    "name",
+    "given_name",
+    "family_name",
+    "preferred_username",
+    "unique_name",
+    "upn",
+    "phone_number",
+    "address",
+];
+
+// A claim that is a json array of strings
+type Strings = Vec<String>;
+
+/// The claims keyed by name, typed accessors are generated for all the known claims
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenClaims(BTreeMap<String, TokenClaim>);
 
 impl From<Object> for TokenClaims {
     fn from(js_obj: Object) -> Self {
-        let mut claims = Vec::new();
+        let mut claims = BTreeMap::new();
         js_sys::Object::entries(&js_obj).for_each(&mut |v, _, _| {
             // If the expected type doesn't match do not return the claim
-            if let Ok(v) = v.try_into() {
-                claims.push(v)
+            if let Ok(v) = TokenClaim::try_from(v) {
+                claims.insert(v.name().to_owned(), v);
             };
         });
         Self(claims)
     }
 }
 
-//TODO: Add an api for this
-impl TokenClaims {}
+/// The payload of a decoded token, see [`Jwt::claims`]
+impl From<&JsonObject> for TokenClaims {
+    fn from(payload: &JsonObject) -> Self {
+        payload
+            .iter()
+            .filter_map(|(key, value)| TokenClaim::from_json(key, value))
+            .collect()
+    }
+}
+
+impl TokenClaims {
+    /// Decodes the payload of a token **without validating the signature**,
+    /// e.g. to read the `scp` or `roles` of an access token
+    pub fn from_jwt(token: &str) -> Result<Self, JwtError> {
+        Ok(Jwt::decode(token)?.claims())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TokenClaim> {
+        self.0.get(name)
+    }
+
+    /// Get any claim as your own type, `None` if missing or of the wrong type.
+    /// Made from the json value, see [`ClaimValue::from_json`]
+    pub fn get_as<T>(&self, name: &str) -> Option<T>
+    where
+        T: ClaimValue,
+    {
+        self.get(name).and_then(|v| T::from_json(&v.to_json()))
+    }
+
+    /// A directory extension claim `extension_<appid>_<name>` by `name`, whatever the app id
+    pub fn extension(&self, name: &str) -> Option<&serde_json::Value> {
+        self.extensions()
+            .find_map(|(_, n, v)| if n == name { Some(v) } else { None })
+    }
+
+    /// All the directory extension claims as `(app_id, name, value)`
+    pub fn extensions(&self) -> impl Iterator<Item = (&str, &str, &serde_json::Value)> {
+        self.0.values().filter_map(|claim| match claim {
+            TokenClaim::custom(key, value) => {
+                let (app_id, name) = key.strip_prefix(EXTENSION_PREFIX)?.split_once('_')?;
+                Some((app_id, name, value))
+            }
+            _ => None,
+        })
+    }
+
+    /// The `oid`, or for Azure AD B2C local accounts without one the `sub`
+    pub fn user_id(&self) -> Option<&str> {
+        self.oid().or_else(|| self.sub()).map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TokenClaim> {
+        self.0.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The space separated `scp` claim
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scp()
+            .map(|v| v.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles().is_some_and(|v| v.iter().any(|v| v == role))
+    }
+
+    pub fn has_any_scope<T>(&self, scopes: &[T]) -> bool
+    where
+        T: AsRef<str>,
+    {
+        let scp = self.scopes();
+        scopes.iter().any(|v| scp.contains(&v.as_ref()))
+    }
+
+    /// False if `scopes` is empty
+    pub fn has_all_scopes<T>(&self, scopes: &[T]) -> bool
+    where
+        T: AsRef<str>,
+    {
+        let scp = self.scopes();
+        !scopes.is_empty() && scopes.iter().all(|v| scp.contains(&v.as_ref()))
+    }
+
+    /// `None` when there are too many groups to fit in the token, in which case they
+    /// need to be fetched from Microsoft Graph
+    pub fn in_group(&self, group: &str) -> Option<bool> {
+        if self.has_groups_overage() {
+            None
+        } else {
+            Some(self.groups().is_some_and(|v| v.iter().any(|v| v == group)))
+        }
+    }
+
+    /// Id tokens from the implicit flow use `hasgroups`, otherwise the `groups` claim
+    /// is replaced by a reference in `_claim_names`
+    /// https://docs.microsoft.com/en-us/azure/active-directory/develop/id-tokens#groups-overage-claim
+    pub fn has_groups_overage(&self) -> bool {
+        self.hasgroups() == Some(&true)
+            || self.claim_names().is_some_and(|v| v.contains_key("groups"))
+    }
+
+    /// The `_claim_sources` endpoint to fetch the groups from on overage
+    pub fn groups_source(&self) -> Option<&str> {
+        let src = self.claim_names()?.get("groups")?.as_str()?;
+        self.claim_sources()?.get(src)?.get("endpoint")?.as_str()
+    }
+
+    /// Unless `pii_logging_enabled`, the same flag as
+    /// [`LoggerOptions::set_pii_logging_enabled`](crate::LoggerOptions::set_pii_logging_enabled),
+    /// the [`PII_CLAIMS`] are removed. They aren't hashed as a hash of a name or email
+    /// is easily reversed by hashing a list of them, use the `oid` to correlate
+    pub fn redacted(&self, pii_logging_enabled: bool) -> Self {
+        if pii_logging_enabled {
+            return self.clone();
+        }
+        self.iter()
+            .filter(|claim| !PII_CLAIMS.contains(&claim.name()))
+            .cloned()
+            .collect()
+    }
+
+    /// Back to a js object, as msal returns them, only available in the browser
+    pub fn to_object(&self) -> Object {
+        let obj = Object::new();
+        for claim in self.iter() {
+            // Can only fail if not an object
+            Reflect::set(&obj, &claim.name().into(), &claim.value()).unwrap();
+        }
+        obj
+    }
+
+    /// A token without an `exp` is treated as expired.
+    /// See [`AuthenticationResult::is_expired`](crate::AuthenticationResult::is_expired)
+    pub fn is_expired(&self, skew: Duration) -> bool {
+        self.exp().is_none_or(|v| time::is_expired(*v, skew))
+    }
+
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.exp().map(|v| time::expires_in(*v))
+    }
+
+    /// See [`AuthenticationResult::should_refresh`](crate::AuthenticationResult::should_refresh)
+    pub fn should_refresh(&self, offset: Duration) -> bool {
+        self.exp().is_none_or(|v| time::should_refresh(*v, offset))
+    }
+}
+
+fn make<T>(
+    f: &dyn Fn(T) -> TokenClaim,
+    key: String,
+    value: JsValue,
+) -> Result<TokenClaim, (String, JsValue)>
+where
+    T: ClaimValue,
+{
+    match T::from_js(&value) {
+        Some(v) => Ok(f(v)),
+        None => Err((key, value)),
+    }
+}
+
+/// As a single entry map, `{ name: value }`
+#[cfg(feature = "serde")]
+impl serde::Serialize for TokenClaim {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.name(), &self.to_json())?;
+        map.end()
+    }
+}
+
+/// As the json object in the token, times are seconds since the unix epoch
+#[cfg(feature = "serde")]
+impl serde::Serialize for TokenClaims {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for claim in self.iter() {
+            map.serialize_entry(claim.name(), &claim.to_json())?;
+        }
+        map.end()
+    }
+}
+
+// Falls back to a custom claim so the raw object is never lost
+fn make_structured<T>(
+    f: &dyn Fn(T) -> TokenClaim,
+    key: String,
+    value: JsValue,
+) -> Result<TokenClaim, (String, JsValue)>
+where
+    T: TryFrom<Object, Error = Object>,
+{
+    if !value.is_object() {
+        return Err((key, value));
+    }
+    match T::try_from(value.unchecked_into()) {
+        Ok(v) => Ok(f(v)),
+        Err(raw) => Ok(TokenClaim::custom(
+            key,
+            js_to_json(&raw).unwrap_or_default(),
+        )),
+    }
+}
+
+fn from_json<T>(
+    f: &dyn Fn(T) -> TokenClaim,
+    _key: &str,
+    value: &serde_json::Value,
+) -> Option<TokenClaim>
+where
+    T: ClaimValue,
+{
+    T::from_json(value).map(f)
+}
+
+// As `make_structured`, natively these are always custom claims
+fn from_json_structured<T>(
+    f: &dyn Fn(T) -> TokenClaim,
+    key: &str,
+    value: &serde_json::Value,
+) -> Option<TokenClaim>
+where
+    T: ClaimValue,
+{
+    if !value.is_object() {
+        return None;
+    }
+    Some(match T::from_json(value) {
+        Some(v) => f(v),
+        None => TokenClaim::custom(key.to_owned(), value.clone()),
+    })
+}
+
+// `Ok(None)` if missing, `Err` if present with the wrong type
+fn get_string(obj: &Object, key: &str) -> Result<Option<String>, ()> {
+    let value = Reflect::get(obj, &key.into()).map_err(|_| ())?;
+    if value.is_undefined() || value.is_null() {
+        Ok(None)
+    } else {
+        value.as_string().map(Some).ok_or(())
+    }
+}
+
+fn get_object(obj: &Object, key: &str) -> Result<Option<Object>, ()> {
+    let value = Reflect::get(obj, &key.into()).map_err(|_| ())?;
+    if value.is_undefined() || value.is_null() {
+        Ok(None)
+    } else if value.is_object() {
+        Ok(Some(value.unchecked_into()))
+    } else {
+        Err(())
+    }
+}
+
+/// OIDC `address` claim
+/// https://openid.net/specs/openid-connect-core-1_0.html#AddressClaim
+#[derive(Clone, Debug, PartialEq)]
+pub struct Address {
+    formatted: Option<String>,
+    street_address: Option<String>,
+    locality: Option<String>,
+    region: Option<String>,
+    postal_code: Option<String>,
+    country: Option<String>,
+    raw: Object,
+}
+
+impl Address {
+    pub fn formatted(&self) -> Option<&str> {
+        self.formatted.as_deref()
+    }
+
+    pub fn street_address(&self) -> Option<&str> {
+        self.street_address.as_deref()
+    }
+
+    pub fn locality(&self) -> Option<&str> {
+        self.locality.as_deref()
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn postal_code(&self) -> Option<&str> {
+        self.postal_code.as_deref()
+    }
+
+    pub fn country(&self) -> Option<&str> {
+        self.country.as_deref()
+    }
+
+    /// The claim as it was in the token
+    pub fn raw(&self) -> &Object {
+        &self.raw
+    }
+}
+
+/// Errors with the raw object if a member has the wrong type
+impl TryFrom<Object> for Address {
+    type Error = Object;
+
+    fn try_from(raw: Object) -> Result<Self, Self::Error> {
+        let parse = || {
+            Ok(Self {
+                formatted: get_string(&raw, "formatted")?,
+                street_address: get_string(&raw, "street_address")?,
+                locality: get_string(&raw, "locality")?,
+                region: get_string(&raw, "region")?,
+                postal_code: get_string(&raw, "postal_code")?,
+                country: get_string(&raw, "country")?,
+                raw: raw.clone(),
+            })
+        };
+        parse().map_err(|()| raw.clone())
+    }
+}
+
+/// The `cnf` claim binding a token to a key, as used by PoP tokens
+/// https://tools.ietf.org/html/rfc7800
+#[derive(Clone, Debug, PartialEq)]
+pub struct Confirmation {
+    jwk: Option<Object>,
+    jkt: Option<String>,
+    x5t_s256: Option<String>,
+    raw: Object,
+}
+
+impl Confirmation {
+    pub fn jwk(&self) -> Option<&Object> {
+        self.jwk.as_ref()
+    }
+
+    /// JWK SHA-256 thumbprint
+    pub fn jkt(&self) -> Option<&str> {
+        self.jkt.as_deref()
+    }
+
+    /// The `x5t#S256` certificate thumbprint
+    pub fn x5t_s256(&self) -> Option<&str> {
+        self.x5t_s256.as_deref()
+    }
+
+    pub fn raw(&self) -> &Object {
+        &self.raw
+    }
+}
+
+impl TryFrom<Object> for Confirmation {
+    type Error = Object;
+
+    fn try_from(raw: Object) -> Result<Self, Self::Error> {
+        let parse = || {
+            Ok(Self {
+                jwk: get_object(&raw, "jwk")?,
+                jkt: get_string(&raw, "jkt")?,
+                x5t_s256: get_string(&raw, "x5t#S256")?,
+                raw: raw.clone(),
+            })
+        };
+        parse().map_err(|()| raw.clone())
+    }
+}
+
+/// The `act` and `may_act` delegation claims, where `act` is the chain of prior actors
+/// https://tools.ietf.org/html/rfc8693#section-4.1
+#[derive(Clone, Debug, PartialEq)]
+pub struct Actor {
+    sub: Option<String>,
+    iss: Option<String>,
+    client_id: Option<String>,
+    act: Option<Box<Actor>>,
+    raw: Object,
+}
+
+impl Actor {
+    pub fn sub(&self) -> Option<&str> {
+        self.sub.as_deref()
+    }
+
+    pub fn iss(&self) -> Option<&str> {
+        self.iss.as_deref()
+    }
+
+    pub fn client_id(&self) -> Option<&str> {
+        self.client_id.as_deref()
+    }
+
+    /// The prior actor
+    pub fn act(&self) -> Option<&Actor> {
+        self.act.as_deref()
+    }
+
+    /// This actor followed by all the prior ones
+    pub fn chain(&self) -> impl Iterator<Item = &Actor> {
+        std::iter::successors(Some(self), |v| v.act())
+    }
+
+    pub fn raw(&self) -> &Object {
+        &self.raw
+    }
+}
+
+impl TryFrom<Object> for Actor {
+    type Error = Object;
+
+    fn try_from(raw: Object) -> Result<Self, Self::Error> {
+        let parse = || {
+            let act = match get_object(&raw, "act")? {
+                Some(v) => Some(Box::new(Self::try_from(v).map_err(|_| ())?)),
+                None => None,
+            };
+            Ok(Self {
+                sub: get_string(&raw, "sub")?,
+                iss: get_string(&raw, "iss")?,
+                client_id: get_string(&raw, "client_id")?,
+                act,
+                raw: raw.clone(),
+            })
+        };
+        parse().map_err(|()| raw.clone())
+    }
+}
+
+impl IntoIterator for TokenClaims {
+    type Item = TokenClaim;
+    type IntoIter = btree_map::IntoValues<String, TokenClaim>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_values()
+    }
+}
+
+impl FromIterator<TokenClaim> for TokenClaims {
+    fn from_iter<I: IntoIterator<Item = TokenClaim>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|v| (v.name().to_owned(), v)).collect())
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -198,21 +1009,10 @@ mod tests {
     fn parse_claims() {
         let id_claims: TokenClaims = idToken.clone().into();
         let access_claims: TokenClaims = accessToken.clone().into();
-        let claim = id_claims
-            .0
-            .iter()
-            .find_map(|v| {
-                if let TokenClaim::alg(c) = v {
-                    Some(c)
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        assert_eq!(claim, "RS256");
+        assert_eq!(id_claims.alg().unwrap(), "RS256");
 
         let no_custom = |claims: TokenClaims| {
-            claims.0.into_iter().find_map(|v| {
+            claims.into_iter().find_map(|v| {
                 if let TokenClaim::custom(c, v) = v {
                     Some((c, v))
                 } else {
@@ -228,4 +1028,220 @@ mod tests {
         assert!(no_custom(access_claims).is_none());
         assert!(no_custom(all).is_none());
     }
+
+    #[test]
+    fn claims_from_jwt() {
+        let claims = TokenClaims::from_jwt(&crate::jwt::tests::access_token()).unwrap();
+        assert_eq!(claims.aud().unwrap(), "api://my-api");
+        assert_eq!(claims.scp().unwrap(), "User.Read Orders.Write");
+        assert_eq!(claims.exp(), Some(&time::from_seconds(1536361411.0)));
+        assert!(claims.is_expired(Duration::ZERO));
+        assert_eq!(claims.expires_in(), Some(Duration::ZERO));
+        assert_eq!(claims.roles().unwrap(), &["Admin", "Reader"]);
+        assert!(TokenClaims::from_jwt("not a token").is_err());
+    }
+
+    #[test]
+    fn groups_overage_from_jwt() {
+        let token = crate::jwt::tests::encode(
+            &serde_json::json!({"alg": "none"}),
+            &serde_json::json!({
+                "_claim_names": {"groups": "src1"},
+                "_claim_sources": {"src1": {"endpoint": "https://graph.microsoft.com/v1.0/users/oid/getMemberObjects"}},
+            }),
+            &[],
+        );
+        let claims = TokenClaims::from_jwt(&token).unwrap();
+        assert!(claims.has_groups_overage());
+        assert_eq!(claims.in_group("group"), None);
+        assert_eq!(
+            claims.groups_source(),
+            Some("https://graph.microsoft.com/v1.0/users/oid/getMemberObjects")
+        );
+
+        let claims = TokenClaims::from_jwt(&crate::jwt::tests::access_token()).unwrap();
+        assert!(!claims.has_groups_overage());
+        assert_eq!(claims.in_group("group"), Some(false));
+    }
+
+    #[wasm_bindgen_test]
+    fn authorization_helpers() {
+        let claims = TokenClaims::from_jwt(&crate::jwt::tests::access_token()).unwrap();
+        assert_eq!(claims.scopes(), ["User.Read", "Orders.Write"]);
+        assert!(claims.has_role("Admin"));
+        assert!(!claims.has_role("Owner"));
+        assert!(claims.has_any_scope(&["Orders.Read", "Orders.Write"]));
+        assert!(claims.has_all_scopes(&["User.Read", "Orders.Write"]));
+        assert!(!claims.has_all_scopes(&["User.Read", "Orders.Read"]));
+        assert!(!claims.has_all_scopes::<&str>(&[]));
+        assert_eq!(claims.in_group("group"), Some(false));
+
+        let overage: TokenClaims = completeToken.clone().into();
+        assert!(overage.has_groups_overage());
+        assert_eq!(overage.in_group("group"), None);
+        assert_eq!(
+            overage.groups_source(),
+            Some("https://graph.microsoft.com/v1.0/users/oid/getMemberObjects")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn structured_claims() {
+        let token = crate::jwt::tests::encode(
+            &serde_json::json!({"alg": "none"}),
+            &serde_json::json!({
+                "address": {"street_address": "1 Queen St", "locality": "Auckland", "country": "NZ"},
+                "cnf": {"jkt": "jkt", "x5t#S256": "x5t"},
+                "act": {"sub": "service_b", "act": {"sub": "service_a", "client_id": "client_a"}},
+                "may_act": {"sub": 13},
+            }),
+            &[],
+        );
+        let claims = TokenClaims::from_jwt(&token).unwrap();
+
+        let address = claims.address().unwrap();
+        assert_eq!(address.street_address(), Some("1 Queen St"));
+        assert_eq!(address.locality(), Some("Auckland"));
+        assert_eq!(address.country(), Some("NZ"));
+        assert_eq!(address.postal_code(), None);
+
+        let cnf = claims.cnf().unwrap();
+        assert_eq!(cnf.jkt(), Some("jkt"));
+        assert_eq!(cnf.x5t_s256(), Some("x5t"));
+        assert!(cnf.jwk().is_none());
+
+        let act = claims.act().unwrap();
+        let chain = act.chain().map(|v| v.sub().unwrap()).collect::<Vec<_>>();
+        assert_eq!(chain, ["service_b", "service_a"]);
+        assert_eq!(act.act().unwrap().client_id(), Some("client_a"));
+
+        // Wrong member type so kept as the raw object
+        assert!(claims.may_act().is_none());
+        assert!(matches!(claims.get("may_act"), Some(TokenClaim::custom(_, v)) if v.is_object()));
+    }
+
+    crate::custom_claims! {
+        struct MyClaims {
+            (ctry, String),
+            (department, String, "extension_b7d8e2c4a1f94a0e8f1d3c6b5a4e2d1f_department"),
+            (clearance, f64),
+            (projects, Vec<String>),
+            (tfp, String),
+            (missing, String),
+        }
+    }
+
+    #[test]
+    fn custom_and_b2c_claims() {
+        let token = crate::jwt::tests::encode(
+            &serde_json::json!({"alg": "none"}),
+            &serde_json::json!({
+                "sub": "local_account",
+                "tfp": "B2C_1_signupsignin",
+                "acr": "b2c_1_signupsignin",
+                "emails": ["abe@example.com"],
+                "newUser": true,
+                "ctry": "NZ",
+                "clearance": "not a number",
+                "projects": ["a", "b"],
+                "extension_b7d8e2c4a1f94a0e8f1d3c6b5a4e2d1f_department": "Sales",
+            }),
+            &[],
+        );
+        let claims = TokenClaims::from_jwt(&token).unwrap();
+
+        assert_eq!(claims.tfp().unwrap(), "B2C_1_signupsignin");
+        assert_eq!(claims.acr().unwrap(), "b2c_1_signupsignin");
+        assert_eq!(claims.emails().unwrap(), &["abe@example.com"]);
+        assert_eq!(claims.newUser(), Some(&true));
+        assert_eq!(claims.user_id(), Some("local_account"));
+        assert_eq!(claims.extension("department").unwrap(), "Sales");
+        assert_eq!(
+            claims
+                .extensions()
+                .map(|(app_id, ..)| app_id)
+                .collect::<Vec<_>>(),
+            ["b7d8e2c4a1f94a0e8f1d3c6b5a4e2d1f"]
+        );
+        assert_eq!(
+            claims.get_as::<String>("tfp").unwrap(),
+            "B2C_1_signupsignin"
+        );
+
+        let mine = MyClaims::from(&claims);
+        assert_eq!(mine.ctry.as_deref(), Some("NZ"));
+        assert_eq!(mine.department.as_deref(), Some("Sales"));
+        assert_eq!(mine.clearance, None);
+        assert_eq!(mine.projects.unwrap(), ["a", "b"]);
+        assert_eq!(mine.tfp.as_deref(), Some("B2C_1_signupsignin"));
+        assert_eq!(mine.missing, None);
+
+        assert_eq!(claims.get("ctry").unwrap().to_json(), "NZ");
+        assert_eq!(
+            claims.get("emails").unwrap().to_json(),
+            serde_json::json!(["abe@example.com"])
+        );
+    }
+
+    #[test]
+    fn redact_claims() {
+        let token = crate::jwt::tests::encode(
+            &serde_json::json!({"alg": "none"}),
+            &serde_json::json!({
+                "oid": "oid",
+                "email": "abe@example.com",
+                "name": "Abe",
+                "address": {"locality": "Auckland"},
+            }),
+            &[],
+        );
+        let claims = TokenClaims::from_jwt(&token).unwrap();
+        assert_eq!(claims.redacted(true), claims);
+
+        let redacted = claims.redacted(false);
+        assert!(redacted.email().is_none());
+        assert!(redacted.name().is_none());
+        assert!(redacted.get("address").is_none());
+        assert_eq!(redacted.oid(), claims.oid());
+        assert_eq!(redacted.len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_claims() {
+        let claims = TokenClaims::from_jwt(&crate::jwt::tests::access_token()).unwrap();
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "aud": "api://my-api",
+                "exp": 1536361411,
+                "scp": "User.Read Orders.Write",
+                "roles": ["Admin", "Reader"],
+            })
+        );
+        let claim = serde_json::to_value(claims.get("aud").unwrap()).unwrap();
+        assert_eq!(claim, serde_json::json!({"aud": "api://my-api"}));
+    }
+
+    #[wasm_bindgen_test]
+    fn query_claims() {
+        let claims: TokenClaims = idToken.clone().into();
+        let oid = claims.oid().unwrap().clone();
+
+        assert!(claims.contains("oid"));
+        assert!(matches!(claims.get("oid"), Some(TokenClaim::oid(v)) if v == &oid));
+        assert_eq!(claims.get("oid").unwrap().name(), "oid");
+        assert!(claims.get("not_a_claim").is_none());
+        assert_eq!(claims.iter().count(), claims.len());
+        assert!(!claims.is_empty());
+
+        let custom: TokenClaims = vec![TokenClaim::custom("ctry".into(), "NZ".into())]
+            .into_iter()
+            .collect();
+        assert!(
+            matches!(custom.get("ctry"), Some(TokenClaim::custom(k, v)) if k == "ctry" && v == "NZ")
+        );
+        assert!(custom.oid().is_none());
+    }
 }
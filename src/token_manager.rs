@@ -0,0 +1,502 @@
+//! Shares tokens between the parts of an app that request them. Silent requests for the
+//! same [`TokenKey`] are merged into a single call to msal, the last result is kept in
+//! memory and refreshed in the background before it expires.
+
+use crate::{
+    clear_cache, msal,
+    requests::{AuthenticationScheme, CacheLookupPolicy, GetBaseAuthRequest, SilentRequest},
+    time, AccountInfo, AuthenticationResult, PublicClientApplication,
+};
+use js_sys::{Object, Promise};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+    time::{Duration, SystemTime},
+};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+/// msal's default `token_renewal_offset_seconds`
+pub const DEFAULT_TOKEN_RENEWAL_OFFSET_SECONDS: u32 = 300;
+/// Stops a token issued with less than the renewal offset left being refreshed in a loop
+const MIN_REFRESH_DELAY: Duration = Duration::from_secs(30);
+
+/// Identifies a token, scopes are compared ignoring case and order
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TokenKey {
+    home_account_id: String,
+    scopes: Vec<String>,
+    authority: Option<String>,
+    scheme: AuthenticationScheme,
+}
+
+impl TokenKey {
+    pub fn home_account_id(&self) -> &str {
+        &self.home_account_id
+    }
+
+    /// Lowercase and sorted
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    pub fn scheme(&self) -> &AuthenticationScheme {
+        &self.scheme
+    }
+}
+
+impl<'a, 'b> From<&'b SilentRequest<'a>> for TokenKey {
+    fn from(request: &'b SilentRequest<'a>) -> Self {
+        let mut scopes = request
+            .scopes()
+            .iter()
+            .map(|v| v.to_lowercase())
+            .collect::<Vec<_>>();
+        scopes.sort();
+        scopes.dedup();
+        Self {
+            home_account_id: request.account().home_account_id().to_owned(),
+            scopes,
+            authority: request.authority().map(String::from),
+            scheme: request.authentication_scheme().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+type Listener = Rc<dyn Fn(&TokenKey, &AuthenticationResult)>;
+
+#[derive(Default)]
+struct Entry {
+    result: Option<AuthenticationResult>,
+    in_flight: Option<Promise>,
+    // Kept for the background refresh, without the caller's claims or cache policy
+    request: Option<SilentRequest<'static>>,
+    // Changes with each new result so stale refresh timers do nothing
+    generation: u64,
+}
+
+/// Wraps the msal app of a [`PopupApp`](crate::popup::PopupApp) or `RedirectApp`. Cheap to
+/// clone, the clones share the same tokens.
+pub struct TokenManager {
+    auth: msal::PublicClientApplication,
+    renewal_offset: Duration,
+    entries: Rc<RefCell<HashMap<TokenKey, Entry>>>,
+    listeners: Rc<RefCell<Vec<Listener>>>,
+    generation: Rc<Cell<u64>>,
+}
+
+impl Clone for TokenManager {
+    fn clone(&self) -> Self {
+        Self {
+            auth: self.auth.clone().unchecked_into(),
+            renewal_offset: self.renewal_offset,
+            entries: self.entries.clone(),
+            listeners: self.listeners.clone(),
+            generation: self.generation.clone(),
+        }
+    }
+}
+
+impl TokenManager {
+    /// Refreshes using the `token_renewal_offset_seconds` of the app's configuration
+    pub fn new<T>(client_app: &T) -> Self
+    where
+        T: PublicClientApplication,
+    {
+        let renewal_offset = client_app
+            .auth()
+            .config()
+            .system()
+            .and_then(|v| v.token_renewal_offset_seconds())
+            .unwrap_or(DEFAULT_TOKEN_RENEWAL_OFFSET_SECONDS);
+        Self {
+            auth: JsValue::clone(client_app.auth()).unchecked_into(),
+            renewal_offset: Duration::from_secs(renewal_offset.into()),
+            entries: Rc::default(),
+            listeners: Rc::default(),
+            generation: Rc::default(),
+        }
+    }
+
+    pub fn renewal_offset(&self) -> Duration {
+        self.renewal_offset
+    }
+
+    /// Returns the cached result, unless it is within the renewal offset of expiring.
+    /// Otherwise joins the request in flight for the same key, or starts one.
+    ///
+    /// A request with `force_refresh` set always starts a new request, which later callers
+    /// join. One with `claims` is passed straight to msal and its result isn't kept, as the
+    /// claims aren't part of the [`TokenKey`].
+    pub async fn acquire_token_silent<'a>(
+        &self,
+        request: &'a SilentRequest<'a>,
+    ) -> Result<AuthenticationResult, JsValue> {
+        if request.claims().is_some() {
+            let promise = self.auth.acquire_token_silent_promise(request.into());
+            return JsFuture::from(promise).await.map(Into::into);
+        }
+        let key = TokenKey::from(request);
+        let force_refresh = request.force_refresh().unwrap_or_default();
+        let promise = {
+            let mut entries = self.entries.borrow_mut();
+            let entry = entries.entry(key.clone()).or_default();
+            if let Some(v) = entry
+                .result
+                .as_ref()
+                .filter(|v| !force_refresh && !v.should_refresh(self.renewal_offset))
+            {
+                return Ok(v.clone());
+            }
+            entry.request = Some(request.to_refresh_template());
+            match &entry.in_flight {
+                Some(v) if !force_refresh => v.clone(),
+                // Replacing the promise in flight means its result is no longer stored
+                _ => entry
+                    .in_flight
+                    .insert(self.auth.acquire_token_silent_promise(request.into()))
+                    .clone(),
+            }
+        };
+        self.settle(key, promise).await
+    }
+
+    /// The last result for the key, even if it has expired
+    pub fn cached(&self, key: &TokenKey) -> Option<AuthenticationResult> {
+        self.entries
+            .borrow()
+            .get(key)
+            .and_then(|v| v.result.clone())
+    }
+
+    /// Called with each new result for a key that already had one, such as from the
+    /// background refresh
+    pub fn on_refreshed<F>(&self, callback: F)
+    where
+        F: Fn(&TokenKey, &AuthenticationResult) + 'static,
+    {
+        self.listeners.borrow_mut().push(Rc::new(callback));
+    }
+
+    /// Forgets the token and stops its background refresh
+    pub fn remove(&self, key: &TokenKey) {
+        self.entries.borrow_mut().remove(key);
+    }
+
+    /// Forgets all the tokens, e.g. on logout
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// Forgets the tokens of the account, or all of them when `None`, then clears msal's
+    /// cache in the same way as the app's `clear_cache`
+    pub async fn clear_cache(&self, account: Option<&AccountInfo>) -> Result<(), JsValue> {
+        match account {
+            Some(account) => self
+                .entries
+                .borrow_mut()
+                .retain(|key, _| key.home_account_id != account.home_account_id()),
+            None => self.clear(),
+        }
+        clear_cache(&self.auth, account).await
+    }
+
+    async fn settle(
+        &self,
+        key: TokenKey,
+        promise: Promise,
+    ) -> Result<AuthenticationResult, JsValue> {
+        let result = JsFuture::from(promise.clone()).await;
+        let mut entries = self.entries.borrow_mut();
+        let entry = match entries.get_mut(&key) {
+            // The first caller to resume stores the result, the rest only convert it
+            Some(entry)
+                if entry
+                    .in_flight
+                    .as_ref()
+                    .is_some_and(|v| Object::is(v, &promise)) =>
+            {
+                entry
+            }
+            _ => return result.map(Into::into),
+        };
+        entry.in_flight = None;
+        // A failed refresh leaves the previous result, which may still be usable
+        let auth_result = AuthenticationResult::from(result?);
+        let refreshed = entry.result.replace(auth_result.clone()).is_some();
+        self.generation.set(self.generation.get() + 1);
+        entry.generation = self.generation.get();
+        let generation = entry.generation;
+        drop(entries);
+
+        self.schedule_refresh(key.clone(), generation, *auth_result.expires_on());
+        if refreshed {
+            // Cloned so a listener can use the manager
+            let listeners = self.listeners.borrow().clone();
+            for listener in listeners {
+                listener(&key, &auth_result)
+            }
+        }
+        Ok(auth_result)
+    }
+
+    fn schedule_refresh(&self, key: TokenKey, generation: u64, expires_on: SystemTime) {
+        let delay = time::expires_in(expires_on)
+            .saturating_sub(self.renewal_offset)
+            .max(MIN_REFRESH_DELAY);
+        let manager = self.clone();
+        spawn_local(async move {
+            time::sleep(delay).await;
+            manager.refresh(key, generation).await;
+        });
+    }
+
+    async fn refresh(&self, key: TokenKey, generation: u64) {
+        let request = self
+            .entries
+            .borrow()
+            .get(&key)
+            .filter(|v| v.generation == generation)
+            .and_then(|v| v.request.clone());
+        if let Some(request) = request {
+            // msal may still consider the cached access token valid
+            let request = request.set_cache_lookup_policy(CacheLookupPolicy::RefreshToken);
+            // Any error is returned to the next caller instead
+            let _ = self.acquire_token_silent(&request).await;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "popup"))]
+pub(crate) mod tests {
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    use super::*;
+    use crate::{
+        popup::PopupApp, requests::SetBaseAuthRequest, tests::*, BrowserAuthOptions,
+        BrowserSystemOptions, Configuration,
+    };
+    use js_sys::{Array, Date, Reflect};
+    use wasm_bindgen::prelude::*;
+    use wasm_bindgen_futures::future_to_promise;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen(module = "/js/msal-object-examples.js")]
+    extern "C" {
+        static authResponse: Object;
+    }
+
+    fn token_manager() -> TokenManager {
+        let auth = BrowserAuthOptions::new(CLIENT_ID).set_authority(AUTHORITY);
+        let system = BrowserSystemOptions::new().set_token_renewal_offset_seconds(60);
+        let config = Configuration::new(auth).set_system(system);
+        TokenManager::new(&PopupApp::new(config))
+    }
+
+    fn request() -> SilentRequest<'static> {
+        SilentRequest::new(&["User.Read", "openid"][..], account()).into_owned()
+    }
+
+    // The example response with a new id and expiry
+    pub(crate) fn response(unique_id: &str, expires_in_seconds: f64) -> JsValue {
+        let response = Object::assign(&Object::new(), &authResponse);
+        let expires_on = Date::new(&(Date::now() + expires_in_seconds * 1000.0).into());
+        Reflect::set(&response, &"uniqueId".into(), &unique_id.into()).unwrap();
+        Reflect::set(&response, &"expiresOn".into(), &expires_on).unwrap();
+        response.into()
+    }
+
+    // As if a result had already been acquired for the key
+    pub(crate) fn seed(manager: &TokenManager, key: TokenKey, result: AuthenticationResult) {
+        let entry = Entry {
+            result: Some(result),
+            ..Entry::default()
+        };
+        manager.entries.borrow_mut().insert(key, entry);
+    }
+
+    #[wasm_bindgen_test]
+    fn token_keys() {
+        let key = TokenKey::from(&request());
+        assert_eq!(key.scopes(), ["openid", "user.read"]);
+        assert_eq!(key.home_account_id(), HOME_ACCOUNT_ID);
+        assert_eq!(key.scheme(), &AuthenticationScheme::Bearer);
+
+        let same = SilentRequest::new(&["openid", "user.read", "User.Read"][..], account());
+        assert_eq!(TokenKey::from(&same), key);
+
+        let pop = request().set_authentication_scheme(AuthenticationScheme::Pop);
+        assert_ne!(TokenKey::from(&pop), key);
+        let authority = request().set_authority(AUTHORITY);
+        assert_ne!(TokenKey::from(&authority), key);
+    }
+
+    #[wasm_bindgen_test]
+    async fn returns_cached_token() {
+        let manager = token_manager();
+        assert_eq!(manager.renewal_offset(), Duration::from_secs(60));
+        let request = request();
+        let key = TokenKey::from(&request);
+        seed(&manager, key.clone(), response("cached", 3600.0).into());
+
+        let auth_result = manager.acquire_token_silent(&request).await.unwrap();
+        assert_eq!(auth_result.unique_id(), "cached");
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "cached");
+
+        manager.clear();
+        assert!(manager.cached(&key).is_none());
+    }
+
+    #[wasm_bindgen_test]
+    async fn clears_the_account() {
+        let manager = token_manager();
+        let key = TokenKey::from(&request());
+        let other = TokenKey {
+            home_account_id: "other".into(),
+            ..key.clone()
+        };
+        seed(&manager, key.clone(), response("cached", 3600.0).into());
+        seed(&manager, other.clone(), response("other", 3600.0).into());
+
+        manager.clear_cache(Some(&account())).await.unwrap();
+        assert!(manager.cached(&key).is_none());
+        assert_eq!(manager.cached(&other).unwrap().unique_id(), "other");
+    }
+
+    #[wasm_bindgen_test]
+    async fn merges_requests_and_notifies() {
+        let manager = token_manager();
+        let request = request();
+        let key = TokenKey::from(&request);
+        // Expiring within the renewal offset, with a refresh already in flight
+        manager.entries.borrow_mut().insert(
+            key.clone(),
+            Entry {
+                result: Some(response("old", 30.0).into()),
+                in_flight: Some(Promise::resolve(&response("new", 3600.0))),
+                ..Entry::default()
+            },
+        );
+        let refreshed = Rc::new(Cell::new(0));
+        let counter = refreshed.clone();
+        manager.on_refreshed(move |_, auth_result| {
+            assert_eq!(auth_result.unique_id(), "new");
+            counter.set(counter.get() + 1);
+        });
+
+        let call = || {
+            let manager = manager.clone();
+            let request = request.clone();
+            future_to_promise(async move {
+                let auth_result = manager.acquire_token_silent(&request).await?;
+                Ok(auth_result.unique_id().into())
+            })
+        };
+        let ids = JsFuture::from(Promise::all(&Array::of2(&call(), &call())))
+            .await
+            .unwrap()
+            .unchecked_into::<Array>()
+            .iter()
+            .map(|v| v.as_string().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["new", "new"]);
+        assert_eq!(refreshed.get(), 1);
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "new");
+    }
+
+    // Stands in for msal, counting the calls
+    fn stub_acquire_token_silent(manager: &TokenManager, calls: Rc<Cell<u32>>) {
+        let silent = Closure::<dyn Fn(JsValue) -> Promise>::new(move |_| {
+            calls.set(calls.get() + 1);
+            Promise::resolve(&response("fresh", 3600.0))
+        });
+        Reflect::set(
+            &manager.auth,
+            &"acquireTokenSilent".into(),
+            &silent.into_js_value(),
+        )
+        .unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn claims_and_force_refresh_skip_the_cache() {
+        let manager = token_manager();
+        let calls = Rc::new(Cell::new(0));
+        stub_acquire_token_silent(&manager, calls.clone());
+        let key = TokenKey::from(&request());
+        manager.entries.borrow_mut().insert(
+            key.clone(),
+            Entry {
+                result: Some(response("cached", 3600.0).into()),
+                in_flight: Some(Promise::resolve(&response("in_flight", 3600.0))),
+                ..Entry::default()
+            },
+        );
+
+        let claims = request().set_claims(r#"{"access_token":{"xms_cc":{"values":["cp1"]}}}"#);
+        let auth_result = manager.acquire_token_silent(&claims).await.unwrap();
+        assert_eq!(auth_result.unique_id(), "fresh");
+        assert_eq!(calls.get(), 1);
+        // Not kept for callers without the claims
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "cached");
+        assert!(manager.entries.borrow()[&key].request.is_none());
+
+        let force_refresh = request()
+            .set_force_refresh(true)
+            .set_cache_lookup_policy(CacheLookupPolicy::Skip);
+        let auth_result = manager.acquire_token_silent(&force_refresh).await.unwrap();
+        assert_eq!(auth_result.unique_id(), "fresh");
+        assert_eq!(calls.get(), 2);
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "fresh");
+
+        let template = manager.entries.borrow()[&key].request.clone().unwrap();
+        assert_eq!(template.force_refresh(), None);
+        assert_eq!(template.cache_lookup_policy(), None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn background_refresh() {
+        let manager = token_manager();
+        let request = request();
+        let key = TokenKey::from(&request);
+        // Issued with less than the 60s renewal offset left
+        manager.entries.borrow_mut().insert(
+            key.clone(),
+            Entry {
+                result: Some(response("old", 30.0).into()),
+                in_flight: Some(Promise::resolve(&response("new", 3600.0))),
+                request: Some(request.clone()),
+                generation: 1,
+            },
+        );
+        let refreshed = Rc::new(Cell::new(0));
+        let counter = refreshed.clone();
+        manager.on_refreshed(move |_, _| counter.set(counter.get() + 1));
+
+        manager.refresh(key.clone(), 1).await;
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "new");
+        assert_eq!(refreshed.get(), 1);
+        let generation = manager.entries.borrow()[&key].generation;
+        assert_ne!(generation, 1);
+
+        // A timer from before the new result does nothing
+        manager
+            .entries
+            .borrow_mut()
+            .get_mut(&key)
+            .unwrap()
+            .in_flight = Some(Promise::resolve(&response("newer", 3600.0)));
+        manager.refresh(key.clone(), 1).await;
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "new");
+        assert_eq!(refreshed.get(), 1);
+
+        manager.refresh(key.clone(), generation).await;
+        assert_eq!(manager.cached(&key).unwrap().unique_id(), "newer");
+        assert_eq!(refreshed.get(), 2);
+    }
+}
@@ -0,0 +1,683 @@
+//! Signature and claims validation of a [`Jwt`] against a JWKS provided by the caller, as defence in depth:
+//! the tokens msal returns have already been validated by the server.
+//! Pure Rust so can be used in both the browser and native services.
+//! https://tools.ietf.org/html/rfc7517
+//! https://tools.ietf.org/html/rfc7518#section-3.1
+
+use crate::{
+    jwt::{Jwt, JwtError},
+    requests::GetAuthorizationUrlRequest,
+    time, TokenClaims,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use p256::{
+    ecdsa::{Signature as EcSignature, VerifyingKey as EcVerifyingKey},
+    EncodedPoint,
+};
+use rsa::{
+    pkcs1v15::{Signature as Pkcs1Signature, VerifyingKey as Pkcs1VerifyingKey},
+    pss::{Signature as PssSignature, VerifyingKey as PssVerifyingKey},
+    signature::Verifier,
+    BigUint, RsaPublicKey,
+};
+use serde_json::Value;
+use sha2::Sha256;
+use std::{borrow::Cow, fmt, str::FromStr};
+use wasm_bindgen::JsValue;
+
+/// msal uses the same default
+pub const DEFAULT_CLOCK_SKEW_SECONDS: u64 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    RS256,
+    PS256,
+    ES256,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RS256 => write!(f, "RS256"),
+            Self::PS256 => write!(f, "PS256"),
+            Self::ES256 => write!(f, "ES256"),
+        }
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = VerifyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "RS256" => Ok(Self::RS256),
+            "PS256" => Ok(Self::PS256),
+            "ES256" => Ok(Self::ES256),
+            _ => Err(VerifyError::UnsupportedAlgorithm(s.to_owned())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    Jwt(JwtError),
+    Jwks(String),
+    UnsupportedAlgorithm(String),
+    /// No key in the JWKS matches the `kid` and algorithm of the token
+    KeyNotFound(Option<String>),
+    InvalidKey(String),
+    InvalidSignature,
+    MissingClaim(&'static str),
+    Expired,
+    NotYetValid,
+    InvalidIssuer,
+    InvalidAudience,
+    InvalidNonce,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Jwt(e) => write!(f, "{}", e),
+            Self::Jwks(e) => write!(f, "invalid jwks: {}", e),
+            Self::UnsupportedAlgorithm(alg) => write!(f, "unsupported algorithm `{}`", alg),
+            Self::KeyNotFound(Some(kid)) => write!(f, "no key found with kid `{}`", kid),
+            Self::KeyNotFound(None) => write!(f, "no key found for the token"),
+            Self::InvalidKey(e) => write!(f, "invalid key: {}", e),
+            Self::InvalidSignature => write!(f, "token signature is invalid"),
+            Self::MissingClaim(claim) => write!(f, "token is missing the `{}` claim", claim),
+            Self::Expired => write!(f, "token has expired"),
+            Self::NotYetValid => write!(f, "token is not valid yet"),
+            Self::InvalidIssuer => write!(f, "token issuer is invalid"),
+            Self::InvalidAudience => write!(f, "token audience is invalid"),
+            Self::InvalidNonce => write!(f, "token nonce does not match the request"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<JwtError> for VerifyError {
+    fn from(err: JwtError) -> Self {
+        Self::Jwt(err)
+    }
+}
+
+impl From<VerifyError> for JsValue {
+    fn from(err: VerifyError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
+
+/// A single public key from a JWKS, only RSA and P-256 EC keys are supported
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jwk {
+    kid: Option<String>,
+    alg: Option<String>,
+    key: PublicKey,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PublicKey {
+    Rsa(RsaPublicKey),
+    Ec(EcVerifyingKey),
+    /// Kept so a token signed by it gives a clear error
+    Unsupported(String),
+}
+
+impl Jwk {
+    fn from_value(jwk: &Value) -> Result<Self, VerifyError> {
+        let get = |name: &str| jwk.get(name).and_then(Value::as_str);
+        let bytes = |name: &'static str| {
+            get(name)
+                .ok_or_else(|| VerifyError::InvalidKey(format!("missing `{}`", name)))
+                .and_then(|v| {
+                    URL_SAFE_NO_PAD.decode(v).map_err(|_| {
+                        VerifyError::InvalidKey(format!("`{}` is not base64url", name))
+                    })
+                })
+        };
+        let key = match (get("kty"), get("crv")) {
+            (Some("RSA"), _) => {
+                let n = BigUint::from_bytes_be(&bytes("n")?);
+                let e = BigUint::from_bytes_be(&bytes("e")?);
+                PublicKey::Rsa(
+                    RsaPublicKey::new(n, e).map_err(|e| VerifyError::InvalidKey(e.to_string()))?,
+                )
+            }
+            (Some("EC"), Some("P-256")) => {
+                let (x, y) = (bytes("x")?, bytes("y")?);
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(VerifyError::InvalidKey(
+                        "P-256 coordinates must be 32 bytes".into(),
+                    ));
+                }
+                let point = EncodedPoint::from_affine_coordinates(
+                    x.as_slice().into(),
+                    y.as_slice().into(),
+                    false,
+                );
+                PublicKey::Ec(
+                    EcVerifyingKey::from_encoded_point(&point)
+                        .map_err(|e| VerifyError::InvalidKey(e.to_string()))?,
+                )
+            }
+            (kty, crv) => PublicKey::Unsupported(format!(
+                "{}{}",
+                kty.unwrap_or("unknown"),
+                crv.map(|v| format!(" {}", v)).unwrap_or_default()
+            )),
+        };
+        Ok(Self {
+            kid: get("kid").map(String::from),
+            alg: get("alg").map(String::from),
+            key,
+        })
+    }
+
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    pub fn alg(&self) -> Option<&str> {
+        self.alg.as_deref()
+    }
+
+    fn verify(&self, alg: Algorithm, message: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
+        let invalid = |_| VerifyError::InvalidSignature;
+        match (&self.key, alg) {
+            (PublicKey::Rsa(key), Algorithm::RS256) => {
+                Pkcs1VerifyingKey::<Sha256>::new(key.clone())
+                    .verify(
+                        message,
+                        &Pkcs1Signature::try_from(signature).map_err(invalid)?,
+                    )
+                    .map_err(invalid)
+            }
+            (PublicKey::Rsa(key), Algorithm::PS256) => PssVerifyingKey::<Sha256>::new(key.clone())
+                .verify(
+                    message,
+                    &PssSignature::try_from(signature).map_err(invalid)?,
+                )
+                .map_err(invalid),
+            (PublicKey::Ec(key), Algorithm::ES256) => key
+                .verify(
+                    message,
+                    &EcSignature::from_slice(signature).map_err(invalid)?,
+                )
+                .map_err(invalid),
+            (PublicKey::Unsupported(kty), _) => Err(VerifyError::InvalidKey(format!(
+                "unsupported key type `{}`",
+                kty
+            ))),
+            _ => Err(VerifyError::InvalidKey(format!(
+                "key type does not match the `{}` algorithm",
+                alg
+            ))),
+        }
+    }
+
+    fn can_verify(&self, alg: Algorithm) -> bool {
+        let key_alg = self.alg.as_deref().is_none_or(|v| v == alg.to_string());
+        let key_type = matches!(
+            (&self.key, alg),
+            (PublicKey::Rsa(_), Algorithm::RS256 | Algorithm::PS256)
+                | (PublicKey::Ec(_), Algorithm::ES256)
+        );
+        key_alg && key_type
+    }
+}
+
+/// The signing keys, e.g. from the `jwks_uri` of the authority's openid configuration
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Jwks(Vec<Jwk>);
+
+impl Jwks {
+    /// Parses a `{"keys": [...]}` document
+    pub fn from_json(json: &str) -> Result<Self, VerifyError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| VerifyError::Jwks(e.to_string()))?;
+        value
+            .get("keys")
+            .and_then(Value::as_array)
+            .ok_or_else(|| VerifyError::Jwks("missing `keys` array".into()))?
+            .iter()
+            .map(Jwk::from_value)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn keys(&self) -> &[Jwk] {
+        &self.0
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.0.iter().find(|v| v.kid() == Some(kid))
+    }
+}
+
+impl FromStr for Jwks {
+    type Err = VerifyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_json(s)
+    }
+}
+
+/// What to check on top of the signature. The `exp` claim is always required.
+///
+/// **Only `exp` and `nbf` are checked by default.** Without [`Validation::set_audience`] and
+/// [`Validation::set_issuer`] a token issued to any other app, or by any other tenant, that is
+/// signed by a key in the JWKS passes, so set both unless you check them yourself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Validation<'a> {
+    algorithms: Vec<Algorithm>,
+    issuer: Option<Cow<'a, str>>,
+    audience: Option<Vec<Cow<'a, str>>>,
+    nonce: Option<Cow<'a, str>>,
+    clock_skew: u64,
+    now: Option<u64>,
+}
+
+impl<'a> Default for Validation<'a> {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![Algorithm::RS256, Algorithm::PS256, Algorithm::ES256],
+            issuer: None,
+            audience: None,
+            nonce: None,
+            clock_skew: DEFAULT_CLOCK_SKEW_SECONDS,
+            now: None,
+        }
+    }
+}
+
+impl<'a> Validation<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_algorithms(mut self, algorithms: &[Algorithm]) -> Self {
+        self.algorithms = algorithms.to_vec();
+        self
+    }
+
+    pub fn set_issuer<T>(mut self, issuer: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// The token is valid if its `aud` matches any of these, for an id token this is the client id
+    pub fn set_audience<T>(mut self, audience: &[T]) -> Self
+    where
+        T: Into<Cow<'a, str>> + Clone,
+    {
+        self.audience = Some(audience.iter().cloned().map(Into::into).collect());
+        self
+    }
+
+    pub fn set_nonce<T>(mut self, nonce: T) -> Self
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Uses the nonce set with `set_nonce` on the request that returned the token, if any.
+    /// A request without one leaves any nonce already set
+    pub fn set_nonce_from_request<R>(mut self, request: &'a R) -> Self
+    where
+        R: GetAuthorizationUrlRequest,
+    {
+        if let Some(nonce) = request.nonce() {
+            self.nonce = Some(nonce.into());
+        }
+        self
+    }
+
+    /// Allowed difference in seconds between the local clock and the server
+    pub fn set_clock_skew(mut self, seconds: u64) -> Self {
+        self.clock_skew = seconds;
+        self
+    }
+
+    /// Validate as if it were this time, in seconds since the unix epoch
+    pub fn set_now(mut self, seconds: u64) -> Self {
+        self.now = Some(seconds);
+        self
+    }
+
+    fn now(&self) -> u64 {
+        self.now
+            .unwrap_or_else(|| time::to_seconds(time::now()) as u64)
+    }
+
+    fn check_claims(&self, jwt: &Jwt) -> Result<(), VerifyError> {
+        let now = self.now();
+        let time = |name: &'static str| jwt.claim(name).and_then(Value::as_f64);
+        let string = |name: &'static str| jwt.claim(name).and_then(Value::as_str);
+
+        let exp = time("exp").ok_or(VerifyError::MissingClaim("exp"))?;
+        if (now as f64) >= exp + self.clock_skew as f64 {
+            return Err(VerifyError::Expired);
+        }
+        if let Some(nbf) = time("nbf") {
+            if (now as f64) + (self.clock_skew as f64) < nbf {
+                return Err(VerifyError::NotYetValid);
+            }
+        }
+        if let Some(issuer) = &self.issuer {
+            let iss = string("iss").ok_or(VerifyError::MissingClaim("iss"))?;
+            if iss != issuer {
+                return Err(VerifyError::InvalidIssuer);
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let aud = match jwt.claim("aud") {
+                Some(Value::String(v)) => vec![v.as_str()],
+                Some(Value::Array(v)) => v.iter().filter_map(Value::as_str).collect(),
+                _ => return Err(VerifyError::MissingClaim("aud")),
+            };
+            if !aud.iter().any(|v| audience.iter().any(|a| a == v)) {
+                return Err(VerifyError::InvalidAudience);
+            }
+        }
+        if let Some(nonce) = &self.nonce {
+            if string("nonce") != Some(nonce) {
+                return Err(VerifyError::InvalidNonce);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Jwt {
+    /// Checks the signature with the matching key from the `jwks`, then the claims.
+    /// The audience and issuer are only checked if set on the [`Validation`]
+    pub fn verify(&self, jwks: &Jwks, validation: &Validation) -> Result<(), VerifyError> {
+        let alg = self
+            .alg()
+            .ok_or(VerifyError::UnsupportedAlgorithm("none".into()))?;
+        let alg = alg.parse::<Algorithm>()?;
+        if !validation.algorithms.contains(&alg) {
+            return Err(VerifyError::UnsupportedAlgorithm(alg.to_string()));
+        }
+        let key = match self.kid() {
+            Some(kid) => jwks.find(kid).filter(|v| v.can_verify(alg)),
+            None => jwks.keys().iter().find(|v| v.can_verify(alg)),
+        }
+        .ok_or_else(|| VerifyError::KeyNotFound(self.kid().map(String::from)))?;
+        key.verify(alg, self.signing_input().as_bytes(), self.signature())?;
+        validation.check_claims(self)
+    }
+}
+
+/// Decodes and verifies a token
+pub fn verify(token: &str, jwks: &Jwks, validation: &Validation) -> Result<Jwt, VerifyError> {
+    let jwt = Jwt::decode(token)?;
+    jwt.verify(jwks, validation)?;
+    Ok(jwt)
+}
+
+impl TokenClaims {
+    /// Like [`TokenClaims::from_jwt`] but the token must pass [`verify`]
+    pub fn from_verified_jwt(
+        token: &str,
+        jwks: &Jwks,
+        validation: &Validation,
+    ) -> Result<Self, VerifyError> {
+        Ok(verify(token, jwks, validation)?.claims())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jwt::tests::encode,
+        requests::{AuthorizationUrlRequest, SetAuthorizationUrlRequest},
+        tests::{CLIENT_ID, SCOPE},
+    };
+    use p256::ecdsa::SigningKey as EcSigningKey;
+    use rand::{rngs::StdRng, SeedableRng};
+    use rsa::{
+        pkcs1v15::SigningKey as Pkcs1SigningKey,
+        pss::BlindedSigningKey as PssSigningKey,
+        signature::{RandomizedSigner, SignatureEncoding, Signer},
+        traits::PublicKeyParts,
+        RsaPrivateKey,
+    };
+    use serde_json::json;
+    use std::sync::OnceLock;
+
+    const ISSUER: &str = "https://login.microsoftonline.com/tenant_id/v2.0";
+    const NONCE: &str = "nonce";
+    const NOW: u64 = 1_600_000_000;
+
+    struct Keys {
+        rsa: RsaPrivateKey,
+        ec: EcSigningKey,
+    }
+
+    // RSA key generation is slow so only done once
+    fn keys() -> &'static Keys {
+        static KEYS: OnceLock<Keys> = OnceLock::new();
+        KEYS.get_or_init(|| {
+            let mut rng = StdRng::seed_from_u64(42);
+            Keys {
+                rsa: RsaPrivateKey::new(&mut rng, 2048).unwrap(),
+                ec: EcSigningKey::random(&mut rng),
+            }
+        })
+    }
+
+    fn b64(bytes: &[u8]) -> String {
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn jwks(keys: &Keys) -> Jwks {
+        let point = keys.ec.verifying_key().to_encoded_point(false);
+        json!({"keys": [
+            {
+                "kty": "RSA",
+                "kid": "rsa",
+                "n": b64(&keys.rsa.n().to_bytes_be()),
+                "e": b64(&keys.rsa.e().to_bytes_be()),
+            },
+            {
+                "kty": "EC",
+                "kid": "ec",
+                "crv": "P-256",
+                "x": b64(point.x().unwrap()),
+                "y": b64(point.y().unwrap()),
+            },
+            { "kty": "oct", "kid": "symmetric", "k": "c2VjcmV0" },
+        ]})
+        .to_string()
+        .parse()
+        .unwrap()
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "exp": NOW + 3600,
+            "nbf": NOW - 10,
+            "nonce": NONCE,
+        })
+    }
+
+    fn sign(keys: &Keys, alg: Algorithm, kid: &str, claims: &Value) -> String {
+        let header = json!({"typ": "JWT", "alg": alg.to_string(), "kid": kid});
+        sign_with_header(keys, alg, &header, claims)
+    }
+
+    fn sign_with_header(keys: &Keys, alg: Algorithm, header: &Value, claims: &Value) -> String {
+        let unsigned = encode(header, claims, &[]);
+        let message = unsigned.trim_end_matches('.');
+        let signature = match alg {
+            Algorithm::RS256 => Pkcs1SigningKey::<Sha256>::new(keys.rsa.clone())
+                .sign(message.as_bytes())
+                .to_vec(),
+            Algorithm::PS256 => PssSigningKey::<Sha256>::new(keys.rsa.clone())
+                .sign_with_rng(&mut StdRng::seed_from_u64(7), message.as_bytes())
+                .to_vec(),
+            Algorithm::ES256 => {
+                let signature: EcSignature = keys.ec.sign(message.as_bytes());
+                signature.to_vec()
+            }
+        };
+        format!("{}.{}", message, b64(&signature))
+    }
+
+    fn validation<'a>() -> Validation<'a> {
+        Validation::new()
+            .set_issuer(ISSUER)
+            .set_audience(&[CLIENT_ID])
+            .set_nonce(NONCE)
+            .set_now(NOW)
+    }
+
+    #[test]
+    fn verify_signatures() {
+        let keys = keys();
+        let jwks = jwks(keys);
+        for (alg, kid) in [
+            (Algorithm::RS256, "rsa"),
+            (Algorithm::PS256, "rsa"),
+            (Algorithm::ES256, "ec"),
+        ] {
+            let token = sign(keys, alg, kid, &claims());
+            assert_eq!(verify(&token, &jwks, &validation()).map(|_| ()), Ok(()));
+
+            // Change the payload but keep the signature
+            let (_, signature) = token.rsplit_once('.').unwrap();
+            let mut tampered = claims();
+            tampered["aud"] = json!("someone else");
+            let tampered = format!(
+                "{}.{}",
+                sign(keys, alg, kid, &tampered).rsplit_once('.').unwrap().0,
+                signature
+            );
+            assert_eq!(
+                verify(&tampered, &jwks, &validation()).map(|_| ()),
+                Err(VerifyError::InvalidSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn verify_keys() {
+        let keys = keys();
+        let jwks = jwks(keys);
+        let check = |alg, kid, validation: &Validation| {
+            verify(&sign(keys, alg, kid, &claims()), &jwks, validation).map(|_| ())
+        };
+
+        assert_eq!(
+            check(Algorithm::RS256, "missing", &validation()),
+            Err(VerifyError::KeyNotFound(Some("missing".into())))
+        );
+        assert_eq!(
+            check(Algorithm::ES256, "rsa", &validation()),
+            Err(VerifyError::KeyNotFound(Some("rsa".into())))
+        );
+        assert_eq!(
+            check(Algorithm::RS256, "symmetric", &validation()),
+            Err(VerifyError::KeyNotFound(Some("symmetric".into())))
+        );
+        assert_eq!(
+            check(
+                Algorithm::RS256,
+                "rsa",
+                &validation().set_algorithms(&[Algorithm::ES256])
+            ),
+            Err(VerifyError::UnsupportedAlgorithm("RS256".into()))
+        );
+        assert!(matches!(
+            Jwks::from_json(r#"{"not_keys": []}"#),
+            Err(VerifyError::Jwks(_))
+        ));
+    }
+
+    #[test]
+    fn verify_without_kid_skips_unsupported_keys() {
+        let keys = keys();
+        let mut jwks = jwks(keys);
+        jwks.0.rotate_right(1);
+        assert_eq!(jwks.keys()[0].kid(), Some("symmetric"));
+
+        let header = json!({"typ": "JWT", "alg": "RS256"});
+        let token = sign_with_header(keys, Algorithm::RS256, &header, &claims());
+        assert_eq!(verify(&token, &jwks, &validation()).map(|_| ()), Ok(()));
+
+        let oct_only = Jwks(jwks.0[..1].to_vec());
+        assert_eq!(
+            verify(&token, &oct_only, &validation()).map(|_| ()),
+            Err(VerifyError::KeyNotFound(None))
+        );
+    }
+
+    #[test]
+    fn verify_claims() {
+        let keys = keys();
+        let jwks = jwks(keys);
+        let token = sign(keys, Algorithm::ES256, "ec", &claims());
+        let check = |validation: Validation| verify(&token, &jwks, &validation).map(|_| ());
+
+        assert_eq!(check(validation().set_now(NOW + 3600 + 299)), Ok(()));
+        assert_eq!(
+            check(validation().set_now(NOW + 3600 + 300)),
+            Err(VerifyError::Expired)
+        );
+        assert_eq!(
+            check(validation().set_now(NOW - 10 - 301)),
+            Err(VerifyError::NotYetValid)
+        );
+        assert_eq!(
+            check(validation().set_now(NOW - 20).set_clock_skew(0)),
+            Err(VerifyError::NotYetValid)
+        );
+        assert_eq!(
+            check(validation().set_issuer("https://evil")),
+            Err(VerifyError::InvalidIssuer)
+        );
+        assert_eq!(
+            check(validation().set_audience(&["other", CLIENT_ID])),
+            Ok(())
+        );
+        assert_eq!(
+            check(validation().set_audience(&["other"])),
+            Err(VerifyError::InvalidAudience)
+        );
+        assert_eq!(
+            check(validation().set_nonce("replayed")),
+            Err(VerifyError::InvalidNonce)
+        );
+
+        let request = AuthorizationUrlRequest::new(&[SCOPE][..]).set_nonce(NONCE);
+        assert_eq!(check(validation().set_nonce_from_request(&request)), Ok(()));
+        let without_nonce = AuthorizationUrlRequest::new(&[SCOPE][..]);
+        assert_eq!(
+            check(
+                validation()
+                    .set_nonce("replayed")
+                    .set_nonce_from_request(&without_nonce)
+            ),
+            Err(VerifyError::InvalidNonce)
+        );
+
+        let mut no_exp = claims();
+        no_exp.as_object_mut().unwrap().remove("exp");
+        let token = sign(keys, Algorithm::ES256, "ec", &no_exp);
+        assert_eq!(
+            verify(&token, &jwks, &validation()).map(|_| ()),
+            Err(VerifyError::MissingClaim("exp"))
+        );
+    }
+}